@@ -1,13 +1,159 @@
-use anyhow::{Context, Result};
-use clap::Parser;
-use song_sheet::{config::Config, run};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use colored::Colorize;
+use song_sheet::{
+    config::{Config, Profile},
+    convert,
+    error::{ConfigError, Location, ParseError},
+    exporter::ExporterType,
+    lint::lint,
+    parse_entries, parse_file,
+    parser::ParserType,
+    run,
+    song::{Song, StanzaType},
+    watch::watch,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     /// Custom config file
-    #[arg(short, long, value_name = "FILE", default_value_t = String::from("config"))]
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        default_value_t = String::from("config"),
+        env = "SONG_SHEET_CONFIG",
+        global = true
+    )]
     config: String,
+
+    /// Profile from the config file to apply, e.g. band
+    #[arg(short, long, env = "SONG_SHEET_PROFILE", global = true)]
+    profile: Option<String>,
+
+    #[command(flatten)]
+    overrides: Overrides,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+/// Values which override the config file and profile, given before or after the subcommand
+#[derive(Args)]
+struct Overrides {
+    /// Name of the song sheet
+    #[arg(long, env = "SONG_SHEET_NAME", global = true)]
+    name: Option<String>,
+
+    /// Song book(s) to read, instead of those in the config file
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "SONG_SHEET_SOURCE",
+        value_delimiter = ';',
+        global = true
+    )]
+    source: Vec<String>,
+
+    /// Format of the song books, e.g. videopsalm, plaintext, which convert, list and show
+    /// otherwise guess from the path
+    #[arg(long, value_name = "FORMAT", env = "SONG_SHEET_FROM", global = true)]
+    from: Option<ParserType>,
+
+    /// Keep the generated LaTeX file
+    #[arg(long, env = "SONG_SHEET_KEEP_TEX", global = true)]
+    keep_tex: bool,
+
+    /// Title of a song to leave out, added to those in the config file
+    #[arg(
+        long,
+        value_name = "TITLE",
+        env = "SONG_SHEET_EXCLUDE",
+        value_delimiter = ';',
+        global = true
+    )]
+    exclude: Vec<String>,
+
+    /// Repair or skip invalid songs rather than failing
+    #[arg(long, env = "SONG_SHEET_LENIENT", global = true)]
+    lenient: bool,
+}
+
+impl From<Overrides> for Profile {
+    fn from(o: Overrides) -> Self {
+        Profile {
+            name: o.name,
+            source: (!o.source.is_empty()).then_some(o.source),
+            from: o.from,
+            keep_tex_file: o.keep_tex.then_some(true),
+            exclude: (!o.exclude.is_empty()).then(|| o.exclude.into_iter().collect()),
+            lenient: o.lenient.then_some(true),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Build the song sheet described by the config file (default)
+    Build,
+
+    /// Write a starter config file, with every option explained
+    Init {
+        /// Look for song books and a cover image in the current directory
+        #[arg(long)]
+        detect: bool,
+
+        /// Overwrite the config file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Convert songs from one format to another
+    Convert {
+        /// Format of the output, e.g. plaintext
+        #[arg(long, value_name = "FORMAT")]
+        to: ExporterType,
+
+        /// Song or song book to convert
+        input: PathBuf,
+
+        /// Output file, or directory when converting a whole song book
+        output: PathBuf,
+    },
+
+    /// List the songs in a song book
+    List {
+        /// Song book, song, or directory of songs
+        #[arg(value_name = "SOURCE")]
+        book: PathBuf,
+    },
+
+    /// Show a single song from a song book
+    Show {
+        /// Song book, song, or directory of songs
+        #[arg(value_name = "SOURCE")]
+        book: PathBuf,
+
+        /// Title of the song, or a unique part of it
+        title: String,
+    },
+
+    /// Check every configured source for problems, without running LaTeX
+    Validate,
+
+    /// Rebuild the song sheet whenever the config file, a source or the cover image changes
+    Watch {
+        /// How often to check for changes, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 500)]
+        interval: u64,
+    },
 }
 
 fn main() -> Result<()> {
@@ -16,6 +162,275 @@ fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    let config = Config::read(&cli.config).with_context(|| "Error reading configuration.")?;
-    run(&config).with_context(|| "Error while creating song sheet.")
+    let result = dispatch(cli);
+    if let Err(e) = &result {
+        print_excerpts(e);
+    }
+    result
+}
+
+/// Shows where in the source things went wrong, if known.
+fn print_excerpts(e: &anyhow::Error) {
+    for c in e.chain() {
+        let at = c
+            .downcast_ref::<ParseError>()
+            .and_then(ParseError::location)
+            .or_else(|| {
+                c.downcast_ref::<ConfigError>()
+                    .and_then(ConfigError::location)
+            });
+        if let Some(at) = at {
+            eprintln!("{}", excerpt(at));
+        }
+    }
+}
+
+fn dispatch(cli: Cli) -> Result<()> {
+    let overrides = Profile::from(cli.overrides);
+    let load_config = || {
+        Config::load(&cli.config, cli.profile.as_deref(), &overrides)
+            .with_context(|| "Error reading configuration.")
+    };
+
+    match cli.command.unwrap_or(Commands::Build) {
+        Commands::Build => {
+            let config = load_config()?;
+            run(&config).with_context(|| "Error while creating song sheet.")
+        }
+        Commands::Init { detect, force } => {
+            let (sources, from, cover) = if detect {
+                Config::detect(".")
+            } else {
+                (Vec::new(), None, None)
+            };
+            if detect && sources.is_empty() {
+                eprintln!(
+                    "{} No song books found, fill in `source` by hand.",
+                    "warning:".yellow().bold()
+                );
+            }
+            Config::init(
+                &cli.config,
+                &sources,
+                from.as_ref(),
+                cover.as_deref(),
+                force,
+            )
+            .with_context(|| "Error writing configuration.")?;
+            println!("Wrote {}.", cli.config);
+            Ok(())
+        }
+        Commands::Convert { to, input, output } => {
+            let from = parser_for(&input, overrides.from.clone())?;
+            convert(&input, &from, &output, &to).with_context(|| "Error while converting songs.")
+        }
+        Commands::List { book } => {
+            let from = parser_for(&book, overrides.from.clone())?;
+            let songs = parse_file(&book, &from).with_context(|| "Error while parsing songs.")?;
+            for song in &songs {
+                println!(
+                    "{}  {}  {} stanzas  {}",
+                    song.title.bold(),
+                    song.author.as_deref().unwrap_or("Unknown author").italic(),
+                    song.stanza_count(),
+                    song.order.dimmed()
+                );
+            }
+            println!("\n{} songs.", songs.len());
+            Ok(())
+        }
+        Commands::Show { book, title } => {
+            let from = parser_for(&book, overrides.from.clone())?;
+            let songs = parse_file(&book, &from).with_context(|| "Error while parsing songs.")?;
+            show(find_song(&songs, &title)?);
+            Ok(())
+        }
+        Commands::Validate => {
+            let config = load_config()?;
+            validate(&config)
+        }
+        Commands::Watch { interval } => watch(
+            &cli.config,
+            cli.profile.as_deref(),
+            &overrides,
+            Duration::from_millis(interval),
+            |e| {
+                let e = anyhow!(e);
+                eprintln!("{} {:#}", "error:".red().bold(), e);
+                print_excerpts(&e);
+            },
+        ),
+    }
+}
+
+/// Prints every problem in the configured sources, failing if there are any.
+fn validate(config: &Config) -> Result<()> {
+    let mut failed = 0;
+    let mut sources = Vec::new();
+    for source in &config.source {
+        match parse_entries(source, &config.from) {
+            Ok(mut entries) => {
+                entries.retain(|e| !config.exclude.contains(&e.title));
+                sources.push((source.clone(), entries));
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("{} {}: {}", "error:".red().bold(), source, e);
+                if let Some(at) = e.location() {
+                    eprintln!("{}", excerpt(at));
+                }
+            }
+        }
+    }
+
+    let problems = lint(sources);
+    for p in &problems {
+        eprintln!(
+            "{} {}: {}: {}",
+            "problem:".yellow().bold(),
+            p.source,
+            p.title.bold(),
+            p.lint
+        );
+    }
+
+    match (failed, problems.len()) {
+        (0, 0) => {}
+        (0, n) => bail!("{} problems found.", n),
+        (f, n) => bail!("{} sources could not be read, {} problems found.", f, n),
+    }
+    println!("{}", "No problems found.".green());
+    Ok(())
+}
+
+/// Renders the offending line of a source, with the column marked.
+fn excerpt(at: &Location) -> String {
+    const WIDTH: usize = 80;
+
+    // Only show part of long lines, such as those in minified JSON
+    let chars: Vec<char> = at.snippet.chars().collect();
+    let column = at.column.saturating_sub(1).min(chars.len());
+    let start = column.saturating_sub(WIDTH / 2);
+    let end = (start + WIDTH).min(chars.len());
+    let mut line: String = chars[start..end].iter().collect();
+    let mut caret = column - start;
+    if start > 0 {
+        line.insert_str(0, "...");
+        caret += 3;
+    }
+    if end < chars.len() {
+        line.push_str("...");
+    }
+
+    let number = at.line.to_string();
+    let pad = " ".repeat(number.len());
+    format!(
+        "{pad}{} {}\n{pad} {}\n{} {} {}\n{pad} {} {}{}",
+        "-->".blue().bold(),
+        at,
+        "|".blue().bold(),
+        number.blue().bold(),
+        "|".blue().bold(),
+        line,
+        "|".blue().bold(),
+        " ".repeat(caret),
+        "^".red().bold(),
+    )
+}
+
+/// Uses the given parser, or guesses one from the path.
+fn parser_for(path: &Path, from: Option<ParserType>) -> Result<ParserType> {
+    from.or_else(|| ParserType::from_path(path)).ok_or_else(|| {
+        anyhow!(
+            r#"Could not tell the format of "{}", please specify it with --from."#,
+            path.display()
+        )
+    })
+}
+
+/// Finds a song by exact title, ignoring case, or else by a unique part of its title.
+fn find_song<'a>(songs: &'a [Song], title: &str) -> Result<&'a Song> {
+    let title = title.trim().to_lowercase();
+    if let Some(song) = songs
+        .iter()
+        .find(|s| s.title.trim().to_lowercase() == title)
+    {
+        return Ok(song);
+    }
+
+    let matches: Vec<&Song> = songs
+        .iter()
+        .filter(|s| s.title.to_lowercase().contains(&title))
+        .collect();
+    match matches.as_slice() {
+        [] => bail!(r#"No song matching "{}"."#, title),
+        [song] => Ok(song),
+        _ => bail!(
+            "Several songs match \"{}\":\n{}",
+            title,
+            matches
+                .iter()
+                .map(|s| format!("  {}", s.title))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+    }
+}
+
+/// Prints a song as readable text.
+fn show(song: &Song) {
+    println!("{}", song.title.bold());
+    if let Some(author) = &song.author {
+        println!("{}", author.italic());
+    }
+    match (&song.composer, &song.tune) {
+        (Some(composer), Some(tune)) => {
+            println!("{}", format!("Music by {} ({})", composer, tune).italic())
+        }
+        (Some(composer), None) => println!("{}", format!("Music by {}", composer).italic()),
+        (None, Some(tune)) => println!("{}", format!("Tune: {}", tune).italic()),
+        (None, None) => {}
+    }
+    if let Some(copyright) = &song.copyright {
+        println!("{}", copyright.dimmed());
+    }
+
+    let mut verse = 0;
+    for (kind, text) in song.stanzas() {
+        println!();
+        match kind {
+            StanzaType::Verse => {
+                verse += 1;
+                println!("{}", format!("Verse {}", verse).bold());
+            }
+            StanzaType::Chorus => println!("{}", "Chorus".bold()),
+            StanzaType::Bridge => println!("{}", "Bridge".bold()),
+        }
+        for line in text.lines() {
+            match kind {
+                StanzaType::Verse => println!("{}", line),
+                _ => println!("  {}", line.italic()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn overrides_go_anywhere() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::parse_from(["song_sheet", "validate", "--lenient", "--source", "a;b"]);
+        assert!(cli.overrides.lenient);
+        assert_eq!(cli.overrides.source, ["a", "b"]);
+
+        let cli = Cli::parse_from(["song_sheet", "--from", "plaintext", "list", "songs"]);
+        assert!(matches!(cli.overrides.from, Some(ParserType::PlainText)));
+        assert!(matches!(cli.command, Some(Commands::List { book }) if book == Path::new("songs")));
+    }
 }
@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, error::ParseError, source_files};
+
+/// Represents the hashes of everything which went into the last successful build
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cache {
+    config: String,
+    inputs: String,
+    tex: String,
+}
+
+impl Cache {
+    /// Hashes the config, and the sources, cover image and number lock file it refers to.
+    ///
+    /// The version of this program counts as part of the config, as another may write different
+    /// LaTeX from the same inputs.
+    pub fn new(config: &Config) -> Result<Self, ParseError> {
+        let mut hasher = Fnv::default();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        format!("{:?}", config).hash(&mut hasher);
+        let config_hash = hasher.finish();
+
+        let mut hasher = Fnv::default();
+        for source in &config.source {
+            for f in source_files(Path::new(source), &config.from)? {
+                f.hash(&mut hasher);
+                hash_file(&f, &mut hasher)?;
+            }
+        }
+        for path in [&config.cover_image, &config.number_lock] {
+            if let Some(path) = path.as_deref().map(Path::new) {
+                if path.exists() {
+                    hash_file(path, &mut hasher)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            config: format!("{:016x}", config_hash),
+            inputs: format!("{:016x}", hasher.finish()),
+            tex: String::new(),
+        })
+    }
+
+    /// Adds the hash of the generated LaTeX file.
+    pub fn with_tex<P>(mut self, tex: &P) -> Result<Self, ParseError>
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        let mut hasher = Fnv::default();
+        hash_file(tex.as_ref(), &mut hasher)?;
+        self.tex = format!("{:016x}", hasher.finish());
+        Ok(self)
+    }
+
+    /// Whether the config and inputs are the same, so the LaTeX would be too.
+    pub fn same_inputs(&self, other: &Self) -> bool {
+        self.config == other.config && self.inputs == other.inputs
+    }
+
+    /// Reads the cache from the last build, if there was one.
+    pub fn load(config: &Config) -> Option<Self> {
+        let path = Self::path(config);
+        let s = fs::read_to_string(&path).ok()?;
+        match toml::from_str(&s) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                warn!("Ignoring invalid cache {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Records the cache for the next build. Failing to do so only costs a rebuild, so is not an
+    /// error.
+    pub fn save(&self, config: &Config) {
+        let path = Self::path(config);
+        trace!("Writing {}.", path.display());
+        let written = toml::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|s| fs::write(&path, s).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Could not write cache {}: {}", path.display(), e);
+        }
+    }
+
+    fn path(config: &Config) -> PathBuf {
+        PathBuf::from(format!(".{}.cache", &config.name))
+    }
+}
+
+fn hash_file(path: &Path, hasher: &mut Fnv) -> Result<(), ParseError> {
+    let bytes = fs::read(path).map_err(|source| ParseError::ReadError {
+        path: path.display().to_string(),
+        source,
+    })?;
+    hasher.write(&bytes);
+    Ok(())
+}
+
+/// FNV-1a, which unlike the standard library's hasher is the same from one release to the next
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
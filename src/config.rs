@@ -1,30 +1,91 @@
-use std::{collections::HashSet, fs, path::Path};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::{error::ConfigError, parser::ParserType};
+use crate::{
+    error::{ConfigError, Location},
+    parser::{self, ParserType},
+};
 use config_defaults::*;
-use serde::Deserialize;
+use log::warn;
+use serde::{Deserialize, Deserializer};
 
 /// Represents the configuration state of the program.
 /// ```
+/// # use std::collections::{BTreeMap, BTreeSet};
+/// # use song_sheet::{config::{Edition, Margins, Normalise, Numbering, Paper, Profile, TitlePage, VerseLabels}, parser::ParserType};
 /// struct Config {
 ///    // Options for LaTeX
 ///    pub keep_tex_file: bool,
 ///    pub name: String,
 ///    pub latex_cmd: String,
 ///    pub latex_args: Vec<String>,
+///    // Skip compiling when nothing changed
+///    pub incremental: bool,
+///    // Keep LaTeX's auxiliary files, so latexmk can reuse them next time
+///    pub keep_aux_files: bool,
 ///
 ///    // Song sheet options
+///    // Image or PDF to use as the cover, if any
 ///    pub cover_image: Option<String>,
+///    // Title page generated from the fields of [title_page], if any
+///    pub title_page: Option<TitlePage>,
 ///    pub chords: bool,
+///    pub paper: Paper,
+///    // Base font size, in points
+///    pub font_size: u8,
+///    pub columns: u8,
+///    pub margins: Margins,
+///    // Keep each song on one page, or allow breaks between stanzas
+///    pub keep_songs_together: bool,
+///    // Also impose the pages as a booklet, to print double-sided, fold and staple
+///    pub booklet: bool,
+///    // One column of large, plain, black text, with stanzas labelled in words
+///    pub large_print: bool,
+///    // Labels printed before each stanza, if any
+///    pub verse_labels: VerseLabels,
+///    pub chorus_label: Option<String>,
+///    pub bridge_label: Option<String>,
+///    // Print a repeated chorus once, with a label where it is repeated
+///    pub compact_choruses: bool,
+///    // Bible verse numbers at the start of lines of psalms, in the margin, or left out
+///    pub bible_verse_numbers: bool,
+///    // How songs are numbered, and where to keep the numbers between builds
+///    pub numbering: Numbering,
+///    pub number_lock: Option<String>,
+///    // Page listing each section with its song count and pages
+///    pub contents: bool,
+///    // Indices of first lines, as well as titles, and of chorus first lines
+///    pub first_line_index: bool,
+///    pub chorus_index: bool,
+///    // Indices of authors and composers, and of tunes, at the back
+///    pub author_index: bool,
+///    pub tune_index: bool,
+///    pub scripture_index: bool,
+///    // Page acknowledging copyrights, grouped by rights holder
+///    pub acknowledgements: bool,
 ///    // List of song titles to exclude
-///    pub exclude: HashSet<String>,
+///    pub exclude: BTreeSet<String>,
+///    // Repair or skip invalid songs instead of failing
+///    pub lenient: bool,
+///    // Fixes for the text of songs, made after reading them
+///    pub normalise: Normalise,
 ///
 ///    // Other options
-///    pub source: String,
+///    // One or more song books
+///    pub source: Vec<String>,
 ///    pub from: ParserType,
+///
+///    // Named sets of overrides, e.g. [profile.band]
+///    pub profile: BTreeMap<String, Profile>,
+///    // Song sheets built from the same songs, e.g. [[edition]]
+///    pub edition: Vec<Edition>,
 /// }
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     // Options for LaTeX
     #[serde(default)]
@@ -35,22 +96,424 @@ pub struct Config {
     pub latex_cmd: String,
     #[serde(default = "default_latex_args")]
     pub latex_args: Vec<String>,
+    /// Skip compiling when the config, the inputs and the generated LaTeX are unchanged
+    #[serde(default = "default_incremental")]
+    pub incremental: bool,
+    /// Keep LaTeX's auxiliary and index files between builds, so latexmk can reuse them
+    #[serde(default)]
+    pub keep_aux_files: bool,
 
     // Song sheet options
-    #[serde(default = "default_cover_image")]
-    pub cover_image: String,
+    /// Image or PDF to use as the cover
+    #[serde(default)]
+    pub cover_image: Option<String>,
+    /// Title page generated from the config, after the cover if there is one
+    #[serde(default)]
+    pub title_page: Option<TitlePage>,
     #[serde(default)]
     pub chords: bool,
+    #[serde(default)]
+    pub paper: Paper,
+    /// Base font size, in points
+    #[serde(default = "default_font_size")]
+    pub font_size: u8,
+    #[serde(default = "default_columns")]
+    pub columns: u8,
+    #[serde(default)]
+    pub margins: Margins,
+    /// Keep each song on one page and in one column, rather than allowing breaks between stanzas
+    #[serde(default = "default_keep_songs_together")]
+    pub keep_songs_together: bool,
+    /// Also write `{name}-booklet.pdf`, with the pages in booklet order, two to a sheet
+    #[serde(default)]
+    pub booklet: bool,
+    /// Lay out for readers with low vision: one column, at least 17pt, black text, no italics,
+    /// and each stanza labelled in words
+    #[serde(default)]
+    pub large_print: bool,
+    #[serde(default)]
+    pub verse_labels: VerseLabels,
+    /// Printed before each chorus, e.g. "Chorus" or "Refrain"
+    #[serde(default)]
+    pub chorus_label: Option<String>,
+    #[serde(default)]
+    pub bridge_label: Option<String>,
+    /// Print the chorus in full only the first time, and a label such as "Chorus (repeat)" after
+    #[serde(default)]
+    pub compact_choruses: bool,
+    /// Print the Bible verse numbers found at the start of lines, e.g. in metrical psalms, as
+    /// superscripts in the margin, rather than leaving them out
+    #[serde(default = "default_bible_verse_numbers")]
+    pub bible_verse_numbers: bool,
+    #[serde(default)]
+    pub numbering: Numbering,
+    /// File which keeps each song's number the same from one build, or edition, to the next
+    #[serde(default)]
+    pub number_lock: Option<String>,
+    /// List each section, with its song count and pages, after the title page
+    #[serde(default)]
+    pub contents: bool,
+    /// Index songs by first line, as well as by title
+    #[serde(default = "default_first_line_index")]
+    pub first_line_index: bool,
+    /// Index songs by the first line of their chorus
+    #[serde(default)]
+    pub chorus_index: bool,
+    /// Index songs by author and composer, at the back
+    #[serde(default = "default_author_index")]
+    pub author_index: bool,
+    /// Index songs by tune, at the back
+    #[serde(default = "default_tune_index")]
+    pub tune_index: bool,
+    /// Index songs by the passages of scripture in their titles, at the back
+    #[serde(default = "default_scripture_index")]
+    pub scripture_index: bool,
+    /// Acknowledge the copyright of each song at the back, grouped by rights holder
+    #[serde(default = "default_acknowledgements")]
+    pub acknowledgements: bool,
     // List of song titles to exclude
     #[serde(default)]
-    pub exclude: HashSet<String>,
+    pub exclude: BTreeSet<String>,
+    /// Repair or skip invalid songs instead of failing, listing them at the end
+    #[serde(default)]
+    pub lenient: bool,
+    /// Fixes for artefacts in the text of songs, made between parsing and writing the LaTeX
+    #[serde(default)]
+    pub normalise: Normalise,
 
     // Other options
-    pub source: String,
+    /// One or more song books, all read with the same parser
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub source: Vec<String>,
     /// Represents the source type, e.g. VideoPsalm, PlainText, ...
+    #[serde(default)]
     pub from: ParserType,
+
+    /// Named sets of overrides, selected with `--profile`
+    #[serde(default)]
+    pub profile: BTreeMap<String, Profile>,
+
+    /// Song sheets to build from the same songs, instead of just the one named above
+    #[serde(default)]
+    pub edition: Vec<Edition>,
+}
+
+/// Represents a paper size
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Paper {
+    #[default]
+    A4,
+    A5,
+    Letter,
+}
+
+impl Paper {
+    /// The document class option selecting this paper size.
+    pub fn latex_option(&self) -> &'static str {
+        match self {
+            Paper::A4 => "a4paper",
+            Paper::A5 => "a5paper",
+            Paper::Letter => "letterpaper",
+        }
+    }
+
+    /// Width and height of a landscape sheet holding two pages side by side, for a booklet.
+    pub fn sheet_size(&self) -> &'static str {
+        match self {
+            Paper::A4 => "420mm,297mm",
+            Paper::A5 => "297mm,210mm",
+            Paper::Letter => "17in,11in",
+        }
+    }
+}
+
+/// Represents the page margins, as LaTeX lengths, e.g. "1cm"
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Margins {
+    #[serde(default = "default_margin")]
+    pub left: String,
+    #[serde(default = "default_margin")]
+    pub right: String,
+    #[serde(default = "default_margin")]
+    pub top: String,
+    #[serde(default = "default_bottom_margin")]
+    pub bottom: String,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Self {
+            left: default_margin(),
+            right: default_margin(),
+            top: default_margin(),
+            bottom: default_bottom_margin(),
+        }
+    }
+}
+
+/// Represents how songs are numbered
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Numbering {
+    /// Songs are not numbered, and indices refer to pages
+    None,
+    /// Songs are numbered in the order they appear
+    #[default]
+    Sequential,
+    /// Songs keep the number given by the source, e.g. their position in the song book, and the
+    /// rest are numbered after them
+    Source,
+}
+
+/// Represents how verses are labelled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerseLabels {
+    #[default]
+    None,
+    /// e.g. "2."
+    Numbers,
+    /// e.g. "Verse Two"
+    Words,
 }
 
+/// Represents the fixes made to the text of songs after they are read, each of which is logged
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Normalise {
+    /// Drop byte order marks and carriage returns, double spaces, and spaces at the ends of lines
+    /// and titles
+    #[serde(default = "default_normalise")]
+    pub whitespace: bool,
+    /// Join words split by copying from a PDF, e.g. "L ORD" or "lai d"
+    #[serde(default = "default_normalise")]
+    pub split_words: bool,
+    #[serde(default)]
+    pub quotes: Quotes,
+    /// Print LORD in small caps. The text of songs is left as it is, this only changes the LaTeX
+    #[serde(default = "default_normalise")]
+    pub lord: bool,
+}
+
+impl Default for Normalise {
+    fn default() -> Self {
+        Self {
+            whitespace: default_normalise(),
+            split_words: default_normalise(),
+            quotes: Quotes::default(),
+            lord: default_normalise(),
+        }
+    }
+}
+
+/// Represents how quotation marks and apostrophes are made consistent
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quotes {
+    /// Left as they are
+    Keep,
+    /// All made ' and "
+    Straight,
+    /// All made ‘ ’ and “ ”, opening or closing by what is around them
+    #[default]
+    Curly,
+}
+
+/// Represents a title page generated from a `[title_page]` section
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TitlePage {
+    /// Defaults to the name of the song sheet
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub church: Option<String>,
+    pub date: Option<String>,
+    pub version: Option<String>,
+    /// Show how many songs and psalms there are, e.g. "312 songs, 150 psalms"
+    #[serde(default)]
+    pub totals: bool,
+}
+
+/// Represents one of several song sheets built from the same songs, from an `[[edition]]`
+/// section, e.g. a chord book alongside the congregation's copy.
+///
+/// Anything left as `None` keeps the config file's value.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Edition {
+    pub name: String,
+    pub chords: Option<bool>,
+    pub paper: Option<Paper>,
+    pub font_size: Option<u8>,
+    pub columns: Option<u8>,
+    pub booklet: Option<bool>,
+    pub large_print: Option<bool>,
+    /// Defaults to labels in words for a large print edition
+    pub verse_labels: Option<VerseLabels>,
+    pub compact_choruses: Option<bool>,
+}
+
+/// Represents a set of overrides for the config file, from a `[profile.*]` section or from the
+/// command line.
+///
+/// Anything left as `None` keeps the config file's value.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub keep_tex_file: Option<bool>,
+    pub name: Option<String>,
+    pub latex_cmd: Option<String>,
+    pub latex_args: Option<Vec<String>>,
+    pub incremental: Option<bool>,
+    pub keep_aux_files: Option<bool>,
+    pub cover_image: Option<String>,
+    pub chords: Option<bool>,
+    pub paper: Option<Paper>,
+    pub font_size: Option<u8>,
+    pub columns: Option<u8>,
+    pub margins: Option<Margins>,
+    pub keep_songs_together: Option<bool>,
+    pub booklet: Option<bool>,
+    pub large_print: Option<bool>,
+    pub verse_labels: Option<VerseLabels>,
+    pub chorus_label: Option<String>,
+    pub bridge_label: Option<String>,
+    pub compact_choruses: Option<bool>,
+    pub bible_verse_numbers: Option<bool>,
+    pub numbering: Option<Numbering>,
+    pub number_lock: Option<String>,
+    pub contents: Option<bool>,
+    pub first_line_index: Option<bool>,
+    pub chorus_index: Option<bool>,
+    pub author_index: Option<bool>,
+    pub tune_index: Option<bool>,
+    pub scripture_index: Option<bool>,
+    pub acknowledgements: Option<bool>,
+    /// Added to the config file's exclusions, rather than replacing them
+    pub exclude: Option<BTreeSet<String>>,
+    pub lenient: Option<bool>,
+    pub normalise: Option<Normalise>,
+    #[serde(default, deserialize_with = "some_one_or_many")]
+    pub source: Option<Vec<String>>,
+    pub from: Option<ParserType>,
+}
+
+impl Profile {
+    /// Overrides the values in `config` with those set here.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(keep_tex_file) = self.keep_tex_file {
+            config.keep_tex_file = keep_tex_file;
+        }
+        if let Some(name) = &self.name {
+            config.name = name.clone();
+        }
+        if let Some(latex_cmd) = &self.latex_cmd {
+            config.latex_cmd = latex_cmd.clone();
+        }
+        if let Some(latex_args) = &self.latex_args {
+            config.latex_args = latex_args.clone();
+        }
+        if let Some(incremental) = self.incremental {
+            config.incremental = incremental;
+        }
+        if let Some(keep_aux_files) = self.keep_aux_files {
+            config.keep_aux_files = keep_aux_files;
+        }
+        if let Some(cover_image) = &self.cover_image {
+            config.cover_image = Some(cover_image.clone());
+        }
+        if let Some(chords) = self.chords {
+            config.chords = chords;
+        }
+        if let Some(paper) = self.paper {
+            config.paper = paper;
+        }
+        if let Some(font_size) = self.font_size {
+            config.font_size = font_size;
+        }
+        if let Some(columns) = self.columns {
+            config.columns = columns;
+        }
+        if let Some(margins) = &self.margins {
+            config.margins = margins.clone();
+        }
+        if let Some(keep_songs_together) = self.keep_songs_together {
+            config.keep_songs_together = keep_songs_together;
+        }
+        if let Some(booklet) = self.booklet {
+            config.booklet = booklet;
+        }
+        if let Some(large_print) = self.large_print {
+            config.large_print = large_print;
+        }
+        match self.verse_labels {
+            Some(verse_labels) => config.verse_labels = verse_labels,
+            // Large print labels in words, whatever the normal print uses
+            None if self.large_print == Some(true) => config.verse_labels = VerseLabels::Words,
+            None => {}
+        }
+        if let Some(chorus_label) = &self.chorus_label {
+            config.chorus_label = Some(chorus_label.clone());
+        }
+        if let Some(bridge_label) = &self.bridge_label {
+            config.bridge_label = Some(bridge_label.clone());
+        }
+        if let Some(compact_choruses) = self.compact_choruses {
+            config.compact_choruses = compact_choruses;
+        }
+        if let Some(bible_verse_numbers) = self.bible_verse_numbers {
+            config.bible_verse_numbers = bible_verse_numbers;
+        }
+        if let Some(numbering) = self.numbering {
+            config.numbering = numbering;
+        }
+        if let Some(number_lock) = &self.number_lock {
+            config.number_lock = Some(number_lock.clone());
+        }
+        if let Some(contents) = self.contents {
+            config.contents = contents;
+        }
+        if let Some(first_line_index) = self.first_line_index {
+            config.first_line_index = first_line_index;
+        }
+        if let Some(chorus_index) = self.chorus_index {
+            config.chorus_index = chorus_index;
+        }
+        if let Some(author_index) = self.author_index {
+            config.author_index = author_index;
+        }
+        if let Some(tune_index) = self.tune_index {
+            config.tune_index = tune_index;
+        }
+        if let Some(scripture_index) = self.scripture_index {
+            config.scripture_index = scripture_index;
+        }
+        if let Some(acknowledgements) = self.acknowledgements {
+            config.acknowledgements = acknowledgements;
+        }
+        if let Some(exclude) = &self.exclude {
+            config.exclude.extend(exclude.iter().cloned());
+        }
+        if let Some(lenient) = self.lenient {
+            config.lenient = lenient;
+        }
+        if let Some(normalise) = &self.normalise {
+            config.normalise = normalise.clone();
+        }
+        if let Some(source) = &self.source {
+            config.source = source.clone();
+        }
+        if let Some(from) = &self.from {
+            config.from = from.clone();
+        }
+    }
+}
+
+/// Font sizes, in points, provided by the standard and extsizes document classes
+const FONT_SIZES: [u8; 8] = [8, 9, 10, 11, 12, 14, 17, 20];
+
 #[rustfmt::skip]
 mod config_defaults {
     pub fn default_name() -> String { String::from("SongSheet") }
@@ -61,11 +524,361 @@ mod config_defaults {
             String::from("-interaction=nonstopmode"),
         ]
     }
-    pub fn default_cover_image() -> String { String::from("cover_image.jpg") }
+    pub fn default_incremental() -> bool { true }
+    pub fn default_font_size() -> u8 { 10 }
+    pub fn default_columns() -> u8 { 2 }
+    pub fn default_margin() -> String { String::from("1cm") }
+    pub fn default_bottom_margin() -> String { String::from("2cm") }
+    pub fn default_keep_songs_together() -> bool { true }
+    pub fn default_bible_verse_numbers() -> bool { true }
+    pub fn default_normalise() -> bool { true }
+    pub fn default_first_line_index() -> bool { true }
+    pub fn default_author_index() -> bool { true }
+    pub fn default_tune_index() -> bool { true }
+    pub fn default_scripture_index() -> bool { true }
+    pub fn default_acknowledgements() -> bool { true }
+}
+
+/// Accepts either a single string or a list of strings.
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    })
+}
+
+fn some_one_or_many<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    one_or_many(deserializer).map(Some)
 }
 
 impl Config {
+    /// Writes a starter config to `path`, with every option documented.
+    ///
+    /// `sources`, `from` and `cover_image` are filled in if given, otherwise placeholders are used.
+    pub fn init<P>(
+        path: &P,
+        sources: &[String],
+        from: Option<&ParserType>,
+        cover_image: Option<&str>,
+        force: bool,
+    ) -> Result<(), ConfigError>
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        let path = path.as_ref();
+        if path.exists() && !force {
+            return Err(ConfigError::AlreadyExists {
+                path: path.display().to_string(),
+            });
+        }
+
+        let template = Self::template(sources, from, cover_image);
+        fs::write(path, template).map_err(|source| ConfigError::WriteError {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    /// Looks in `dir`, and the directories in it, for song books and a cover image.
+    ///
+    /// Prefers VideoPsalm song books, then directories of plain text songs.
+    pub fn detect<P>(dir: &P) -> (Vec<String>, Option<ParserType>, Option<String>)
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        let list = |dir: &Path| -> Vec<PathBuf> {
+            let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+                .map(|d| d.filter_map(|e| e.ok().map(|e| e.path())).collect())
+                .unwrap_or_default();
+            paths.sort();
+            paths
+        };
+        let has_ext = |p: &Path, exts: &[&str]| {
+            p.is_file()
+                && p.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| exts.contains(&e.to_lowercase().as_str()))
+        };
+        let relative = |p: &Path| {
+            p.strip_prefix(dir.as_ref())
+                .unwrap_or(p)
+                .display()
+                .to_string()
+        };
+
+        let top = list(dir.as_ref());
+        let dirs: Vec<&PathBuf> = top
+            .iter()
+            .filter(|p| p.is_dir())
+            .filter(|p| {
+                !p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+            })
+            .collect();
+
+        let cover = top
+            .iter()
+            .find(|p| has_ext(p, &["jpg", "jpeg", "png", "pdf"]))
+            .map(|p| relative(p));
+
+        let mut books: Vec<String> = top
+            .iter()
+            .cloned()
+            .chain(dirs.iter().flat_map(|d| list(d)))
+            .filter(|p| has_ext(p, &["json"]))
+            // Only song books, not any other JSON
+            .filter(|p| fs::read_to_string(p).is_ok_and(|s| parser::is_video_psalm(&s)))
+            .map(|p| relative(&p))
+            .collect();
+        if !books.is_empty() {
+            books.sort();
+            return (books, Some(ParserType::VideoPsalm), cover);
+        }
+
+        let songs: Vec<String> = std::iter::once(dir.as_ref())
+            .chain(dirs.iter().map(|d| d.as_path()))
+            .filter(|d| list(d).iter().any(|p| has_ext(p, &["txt"])))
+            .map(|d| match relative(d) {
+                d if d.is_empty() => String::from("."),
+                d => d,
+            })
+            .collect();
+        if !songs.is_empty() {
+            return (songs, Some(ParserType::PlainText), cover);
+        }
+
+        (Vec::new(), None, cover)
+    }
+
+    /// A config file with every option and its default, explained.
+    fn template(
+        sources: &[String],
+        from: Option<&ParserType>,
+        cover_image: Option<&str>,
+    ) -> String {
+        let quote = |s: &str| toml::Value::String(s.to_owned()).to_string();
+        let list = |v: &[String]| {
+            format!(
+                "[{}]",
+                v.iter().map(|s| quote(s)).collect::<Vec<_>>().join(", ")
+            )
+        };
+
+        let source = match sources {
+            [] => quote("songs.json"),
+            [one] => quote(one),
+            many => format!(
+                "[\n{}\n]",
+                many.iter()
+                    .map(|s| format!("    {},", quote(s)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        };
+        let from = from.unwrap_or(&ParserType::VideoPsalm);
+        let cover_image = match cover_image {
+            Some(cover_image) => format!("cover_image = {}", quote(cover_image)),
+            None => String::from(r#"# cover_image = "cover.jpg""#),
+        };
+
+        format!(
+            r#"# Song sheet configuration.
+
+# ====   Songs   ====
+
+# Song book(s) to read. Either a single path or a list of paths.
+# Plain text songs are one per file, so a directory of them may be given instead.
+source = {source}
+
+# Format of the song books: "VideoPsalm" or "PlainText".
+from = {from}
+
+# Titles of songs to leave out.
+exclude = []
+
+# Repair or skip invalid songs, listing them at the end, rather than stopping at the first one.
+lenient = false
+
+# Fixes for the text of songs, each logged as it is made:
+#  - whitespace: drop byte order marks, carriage returns, double spaces and trailing spaces
+#  - split_words: join words split by copying from a PDF, e.g. "L ORD" or "lai d"
+#  - quotes: make quotation marks "curly", "straight", or "keep" them as they are
+#  - lord: print LORD in small caps
+normalise = {{ whitespace = true, split_words = true, quotes = "curly", lord = true }}
+
+# ====   Song sheet   ====
+
+# Name of the song sheet. The LaTeX file and PDF are named after it.
+name = {name}
+
+# Image (JPEG or PNG) or PDF to use as the cover. Leave out for no cover.
+{cover_image}
+
+# Whether to include chords.
+chords = false
+
+# Paper size: "a4", "a5" or "letter".
+paper = "a4"
+
+# Base font size in points: 8, 9, 10, 11, 12, 14, 17 or 20.
+font_size = {font_size}
+
+# Number of columns of songs, from 1 to 10.
+columns = {columns}
+
+# Page margins, as LaTeX lengths.
+margins = {{ left = {margin}, right = {margin}, top = {margin}, bottom = {bottom_margin} }}
+
+# Keep each song on one page and in one column, or allow it to break between stanzas so long
+# hymns don't overflow or leave big gaps.
+keep_songs_together = true
+
+# Also write a PDF named "<name>-booklet.pdf" for printing a folded booklet: the pages are padded
+# to a multiple of four and put in booklet order, two to each side of a sheet twice the size of
+# `paper`. With the default A4 paper that makes an A4 booklet on A3 sheets, so for an A5 booklet
+# printed on A4, set `paper = "a5"`.
+booklet = false
+
+# Lay out for readers with low vision: a single column, at least 17pt, black text, no italic
+# choruses, and each stanza labelled in words, e.g. "Verse Two", unless labelled otherwise below.
+# The PDF is also tagged for screen readers when the LaTeX installation supports it.
+large_print = false
+
+# How verses are labelled: "none", "numbers" (e.g. "2.") or "words" (e.g. "Verse Two").
+verse_labels = "none"
+
+# Labels printed before each chorus and bridge. Leave out for none.
+# chorus_label = "Chorus"
+# bridge_label = "Bridge"
+
+# Print a repeated chorus in full only the first time, and "Chorus (repeat)" (or the chorus label
+# followed by "(repeat)") where it comes again.
+compact_choruses = false
+
+# Print the Bible verse numbers which start lines of psalms and other passages of scripture, e.g.
+# "5 He grew up like a tender shoot", as small numbers in the margin. Set to false to leave them
+# out.
+bible_verse_numbers = true
+
+# How songs are numbered: "sequential", "source" to keep numbers from the song books (a song's
+# position in its VideoPsalm song book, or the number it was given there, or plain text file names
+# such as "42 Abide with Me.txt"), or "none" for no numbers.
+# Indices refer to song numbers, or to pages if there are none.
+numbering = "sequential"
+
+# File which keeps each song's number the same from one build, or edition, to the next, so new
+# songs are numbered after the rest rather than moving them along. Leave out to renumber each time.
+# number_lock = "numbers.lock"
+
+# List each section, with how many songs it has and the pages they are on, after the title page.
+# There is a section for each song book, or directory of plain text songs.
+contents = false
+
+# Index songs by their first line as well as their title, with first lines which differ from the
+# title in italics, and optionally by the first line of their chorus.
+first_line_index = true
+chorus_index = false
+
+# At the back, index songs by author and composer, by tune, and by the passages of scripture in
+# their titles, e.g. "Isaiah 53:5-9", in the order of the books of the Bible. Also acknowledge the
+# copyright of each song, grouped by rights holder. Each is left out if no song has the details.
+author_index = true
+tune_index = true
+scripture_index = true
+acknowledgements = true
+
+# ====   LaTeX   ====
+
+# Command used to compile the LaTeX, and the arguments given to it.
+latex_cmd = {latex_cmd}
+latex_args = {latex_args}
+
+# Keep the generated LaTeX file after compiling.
+keep_tex_file = false
+
+# Skip compiling when nothing has changed since the last build.
+incremental = true
+
+# Keep LaTeX's auxiliary and index files between builds, so latexmk can reuse them when only the
+# songs have changed.
+keep_aux_files = false
+
+# ====   Title page   ====
+
+# Title page generated from these fields, after the cover if there is one. Every field is
+# optional, and the title defaults to the name. Leave out for no title page, e.g.
+#
+# [title_page]
+# title = "Songs of Praise"
+# subtitle = "Hymns, psalms and songs"
+# church = "St Andrew's Church"
+# date = "Easter 2024"
+# version = "2.1"
+# # Show how many songs and psalms there are, e.g. "312 songs, 150 psalms"
+# totals = true
+
+# ====   Profiles   ====
+
+# Named sets of values which replace those above when chosen with `--profile`, e.g.
+#
+# [profile.band]
+# name = "BandBook"
+# chords = true
+
+# ====   Editions   ====
+
+# Song sheets to build from the same songs in one go, instead of just the one named above. Each
+# has its own name, and may have its own chords, paper, font_size, columns, booklet, large_print,
+# verse_labels and compact_choruses. A large print edition labels verses in words unless it sets
+# its own verse_labels. For example:
+#
+# [[edition]]
+# name = "Congregation"
+# compact_choruses = true
+#
+# [[edition]]
+# name = "LargePrint"
+# large_print = true
+"#,
+            source = source,
+            from = quote(&from.to_string()),
+            name = quote(&default_name()),
+            cover_image = cover_image,
+            latex_cmd = quote(&default_latex_cmd()),
+            latex_args = list(&default_latex_args()),
+            font_size = default_font_size(),
+            columns = default_columns(),
+            margin = quote(&default_margin()),
+            bottom_margin = quote(&default_bottom_margin()),
+        )
+    }
+
     pub fn read<P>(path: &P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        Self::load(path, None, &Profile::default())
+    }
+
+    /// Reads the config file, then applies the named profile and then `overrides` on top.
+    pub fn load<P>(
+        path: &P,
+        profile: Option<&str>,
+        overrides: &Profile,
+    ) -> Result<Self, ConfigError>
     where
         P: AsRef<Path> + ?Sized,
     {
@@ -73,7 +886,181 @@ impl Config {
             path: path.as_ref().display().to_string(),
             source,
         })?;
-        let c: Config = toml::from_str(&s)?;
+        let mut c: Config = toml::from_str(&s).map_err(|e| match e.span() {
+            Some(span) => ConfigError::InvalidConfig {
+                at: Location::from_offset(&path.as_ref().display().to_string(), &s, span.start),
+                message: e.message().to_owned(),
+            },
+            None => ConfigError::TOMLError(e),
+        })?;
+
+        if let Some(name) = profile {
+            let p = c
+                .profile
+                .get(name)
+                .ok_or_else(|| ConfigError::UnknownProfile {
+                    name: name.to_owned(),
+                    available: c.profile.keys().cloned().collect::<Vec<_>>().join(", "),
+                })?
+                .clone();
+            p.apply(&mut c);
+        }
+        overrides.apply(&mut c);
+
+        c.check()?;
         Ok(c)
     }
+
+    /// Checks that the files the config refers to exist.
+    fn check(&self) -> Result<(), ConfigError> {
+        if self.source.is_empty() {
+            return Err(ConfigError::NoSource);
+        }
+
+        if let Some(path) = self.source.iter().find(|s| !Path::new(s).exists()) {
+            return Err(ConfigError::MissingSource { path: path.clone() });
+        }
+
+        if let Some(cover_image) = &self.cover_image {
+            if !Path::new(cover_image).exists() {
+                return Err(ConfigError::MissingCoverImage {
+                    path: cover_image.clone(),
+                });
+            }
+            let supported = Path::new(cover_image)
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| {
+                    ["pdf", "png", "jpg", "jpeg"].contains(&e.to_lowercase().as_str())
+                });
+            if !supported {
+                return Err(ConfigError::UnsupportedCoverImage {
+                    path: cover_image.clone(),
+                });
+            }
+        }
+
+        let mut names = BTreeSet::new();
+        for edition in self.editions() {
+            if !FONT_SIZES.contains(&edition.font_size) {
+                return Err(ConfigError::UnsupportedFontSize {
+                    size: edition.font_size,
+                });
+            }
+            if !(1..=10).contains(&edition.columns) {
+                return Err(ConfigError::UnsupportedColumns {
+                    columns: edition.columns,
+                });
+            }
+            if edition.booklet && edition.paper == Paper::A4 {
+                warn!(
+                    r#"The booklet of {} will be A4 pages on A3 sheets. For an A5 booklet printed on A4, set paper = "a5"."#,
+                    edition.name
+                );
+            }
+            if !names.insert(edition.name.clone()) {
+                return Err(ConfigError::DuplicateEdition { name: edition.name });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Path of the compiled song sheet.
+    pub fn pdf_path(&self) -> PathBuf {
+        PathBuf::from(format!("{}.pdf", self.name))
+    }
+
+    /// Path of the song sheet imposed as a booklet.
+    pub fn booklet_path(&self) -> PathBuf {
+        PathBuf::from(format!("{}-booklet.pdf", self.name))
+    }
+
+    /// The song sheets to build: one per edition, or just this one if there are none.
+    pub fn editions(&self) -> Vec<Config> {
+        if self.edition.is_empty() {
+            return vec![self.clone()];
+        }
+
+        self.edition
+            .iter()
+            .map(|e| {
+                let mut c = self.clone();
+                // Changing one edition shouldn't make the others look changed
+                c.edition.clear();
+                c.name = e.name.clone();
+                if let Some(chords) = e.chords {
+                    c.chords = chords;
+                }
+                if let Some(paper) = e.paper {
+                    c.paper = paper;
+                }
+                if let Some(font_size) = e.font_size {
+                    c.font_size = font_size;
+                }
+                if let Some(columns) = e.columns {
+                    c.columns = columns;
+                }
+                if let Some(booklet) = e.booklet {
+                    c.booklet = booklet;
+                }
+                if let Some(large_print) = e.large_print {
+                    c.large_print = large_print;
+                }
+                c.verse_labels = match e.verse_labels {
+                    Some(verse_labels) => verse_labels,
+                    // Not the labels chosen for the normal print
+                    None if e.large_print == Some(true) => VerseLabels::Words,
+                    None => c.verse_labels,
+                };
+                if let Some(compact_choruses) = e.compact_choruses {
+                    c.compact_choruses = compact_choruses;
+                }
+                c
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incremental_by_default() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(config.incremental);
+        assert!(!config.keep_aux_files);
+    }
+
+    #[test]
+    fn large_print_edition_labels_in_words() {
+        let config: Config = toml::from_str(
+            r#"
+verse_labels = "numbers"
+
+[[edition]]
+name = "Normal"
+
+[[edition]]
+name = "LargePrint"
+large_print = true
+
+[[edition]]
+name = "LargePrintNumbered"
+large_print = true
+verse_labels = "numbers"
+"#,
+        )
+        .unwrap();
+        let labels: Vec<VerseLabels> = config.editions().iter().map(|e| e.verse_labels).collect();
+        assert_eq!(
+            labels,
+            [
+                VerseLabels::Numbers,
+                VerseLabels::Words,
+                VerseLabels::Numbers
+            ]
+        );
+    }
 }
@@ -1,9 +1,7 @@
-use std::io;
+use std::{fmt::Display, io};
 
 use thiserror::Error;
 
-use crate::parser::ParserType;
-
 /// Represents any kind of error which can occur.
 #[derive(Debug, Error)]
 #[error("An error occured.")]
@@ -17,9 +15,23 @@ pub enum SongSheetError {
     /// Represents an error while parsing sources
     ParseError(#[from] ParseError),
 
+    /// Represents an error while exporting songs
+    ExportError(#[from] ExportError),
+
     /// Represents an error reading from input.
     #[error(r#"Could not read "{}"."#, .path)]
     ReadError { path: String, source: io::Error },
+
+    /// Represents an error writing output other than the song sheet itself.
+    #[error(r#"Could not write "{}"."#, .path)]
+    WriteError { path: String, source: io::Error },
+
+    /// Represents a song number lock file which could not be understood.
+    #[error(r#"Invalid song number lock file "{}"."#, .path)]
+    InvalidLock {
+        path: String,
+        source: toml::de::Error,
+    },
 }
 
 /// Represents an error which can occur while obtaining the configuration
@@ -33,6 +45,60 @@ pub enum ConfigError {
     /// Represents an error from de-serializing toml
     #[error("Error while de-serializing toml.")]
     TOMLError(#[from] toml::de::Error),
+
+    /// Represents an invalid value or unknown key in the config.
+    #[error("Invalid config at {at}: {message}")]
+    InvalidConfig { at: Location, message: String },
+
+    /// When the selected profile is not in the config.
+    #[error(r#"Unknown profile "{name}", available profiles are: {available}."#)]
+    UnknownProfile { name: String, available: String },
+
+    /// When no source is given, in the config or otherwise.
+    #[error("No source specified.")]
+    NoSource,
+
+    /// When a configured source does not exist.
+    #[error(r#"Source "{path}" does not exist."#)]
+    MissingSource { path: String },
+
+    /// When the configured cover image does not exist.
+    #[error(r#"Cover image "{path}" does not exist."#)]
+    MissingCoverImage { path: String },
+
+    /// When the configured cover image is not a format LaTeX can include.
+    #[error(r#"Cover image "{path}" is not a PDF, PNG or JPEG file."#)]
+    UnsupportedCoverImage { path: String },
+
+    /// When a font size is not one LaTeX provides.
+    #[error("Font size {size}pt is not supported, use one of 8, 9, 10, 11, 12, 14, 17 or 20.")]
+    UnsupportedFontSize { size: u8 },
+
+    /// When there are too few or too many columns for LaTeX.
+    #[error("{columns} columns is not supported, use from 1 to 10.")]
+    UnsupportedColumns { columns: u8 },
+
+    /// When two editions would write to the same files.
+    #[error(r#"More than one edition is named "{name}"."#)]
+    DuplicateEdition { name: String },
+
+    /// Represents an error while writing a new config.
+    #[error(r#"Could not write "{}"."#, .path)]
+    WriteError { path: String, source: io::Error },
+
+    /// When a new config would overwrite an existing file.
+    #[error(r#""{path}" already exists."#)]
+    AlreadyExists { path: String },
+}
+
+impl ConfigError {
+    /// Where in the config the error occured, if known.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::InvalidConfig { at, .. } => Some(at),
+            _ => None,
+        }
+    }
 }
 
 /// Represents an error relating to LaTeX
@@ -61,8 +127,19 @@ pub enum LaTeXError {
 #[error("An error occured while parsing sources.")]
 pub enum ParseError {
     /// Represents an error while parsing JSON.
-    #[error("Error while parsing JSON.")]
-    JSONError(#[from] serde_json::Error),
+    #[error("Error while parsing JSON at {at}.")]
+    JSONError {
+        at: Location,
+        source: serde_json::Error,
+    },
+
+    /// When a plain text song title is not followed by a blank line.
+    #[error("Invalid song title at {at}: the title must be followed by a blank line.")]
+    InvalidTitle { at: Location },
+
+    /// When a plain text stanza starts with an unknown tag.
+    #[error(r##"Invalid tag "{tag}" at {at}: expected "#c" or "#b" on its own line."##)]
+    InvalidTag { at: Location, tag: String },
 
     /// Represents an error setting the order of stanzas in a song.
     #[error("Error setting stanza order: invalid order.")]
@@ -72,15 +149,78 @@ pub enum ParseError {
     #[error(r#"Could not read "{}"."#, .path)]
     ReadError { path: String, source: io::Error },
 
-    /// When the selected parser is not available
-    #[error(r#""{0}" parser is not available."#)]
-    NotAvailable(ParserType),
-
     /// When the specified parser is unknown
     #[error(r#"Unknown parser type "{0}"."#)]
     UnknownParser(String),
 }
 
+impl ParseError {
+    /// Where in the source the error occured, if known.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::JSONError { at, .. }
+            | Self::InvalidTitle { at }
+            | Self::InvalidTag { at, .. } => Some(at),
+            _ => None,
+        }
+    }
+}
+
+/// Represents a position in a source file
+#[derive(Debug, Clone, Default)]
+pub struct Location {
+    pub path: String,
+    /// Line number, starting from 1
+    pub line: usize,
+    /// Column in characters, starting from 1
+    pub column: usize,
+    /// The whole of the offending line
+    pub snippet: String,
+}
+
+impl Location {
+    /// Location of a line and column in `input`, read from `path`.
+    pub fn new(path: &str, input: &str, line: usize, column: usize) -> Self {
+        Self {
+            path: path.to_owned(),
+            line,
+            column,
+            snippet: input
+                .lines()
+                .nth(line.saturating_sub(1))
+                .unwrap_or_default()
+                .to_owned(),
+        }
+    }
+
+    /// Location of the byte `offset` into `input`, read from `path`.
+    pub fn from_offset(path: &str, input: &str, offset: usize) -> Self {
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        Self::new(path, input, line, column)
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.column)
+    }
+}
+
+/// Represents an error while exporting songs
+#[derive(Debug, Error)]
+#[error("An error occured while exporting songs.")]
+pub enum ExportError {
+    /// Represents an error writing to output.
+    #[error(r#"Could not write "{}"."#, .path)]
+    WriteError { path: String, source: io::Error },
+
+    /// When the specified exporter is unknown
+    #[error(r#"Unknown export type "{0}"."#)]
+    UnknownExporter(String),
+}
+
 /// Represents an error relating to a particular song
 #[derive(Debug, Error)]
 #[error("An error occured while constructing a song.")]
@@ -106,4 +246,28 @@ pub enum SongError {
         expected: usize,
         actual: usize,
     },
+
+    /// When the order contains a character which is not a stanza type.
+    #[error(r#"Unknown stanza '{found}' at position {position} in the order of "{song_title}"."#)]
+    UnknownStanza {
+        song_title: String,
+        found: char,
+        position: usize,
+    },
+
+    /// When a song has verses which the order never calls for. Only a warning.
+    #[error(r#"Order calls for {used} verses, but {actual} specified for "{song_title}"."#)]
+    UnusedVerses {
+        song_title: String,
+        used: usize,
+        actual: usize,
+    },
+
+    /// When a song has a chorus which the order never calls for. Only a warning.
+    #[error(r#"Chorus specified, but order never calls for it in "{song_title}"."#)]
+    UnusedChorus { song_title: String },
+
+    /// When a song has a bridge which the order never calls for. Only a warning.
+    #[error(r#"Bridge specified, but order never calls for it in "{song_title}"."#)]
+    UnusedBridge { song_title: String },
 }
@@ -0,0 +1,60 @@
+mod plain_text;
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{error::ExportError, song::Song};
+
+pub use plain_text::plain_text;
+
+#[derive(Debug, Clone)]
+pub enum ExporterType {
+    PlainText,
+}
+
+impl ExporterType {
+    /// File extension used for songs written in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::PlainText => "txt",
+        }
+    }
+
+    /// Renders `song` in this format.
+    pub fn export(&self, song: &Song) -> String {
+        match self {
+            Self::PlainText => plain_text(song),
+        }
+    }
+}
+
+impl Display for ExporterType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PlainText => f.write_str("PlainText"),
+        }
+    }
+}
+
+impl FromStr for ExporterType {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut norm = s.to_lowercase();
+        norm.retain(|c| !matches!(c, ' ' | '-' | '_'));
+
+        match norm.as_str() {
+            "plaintext" => Ok(Self::PlainText),
+            _ => Err(ExportError::UnknownExporter(s.to_owned())),
+        }
+    }
+}
+
+/// Turns a song title into something safe to use as a file name.
+pub fn file_stem(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
@@ -0,0 +1,40 @@
+use crate::song::{Song, StanzaType};
+
+/// Writes a song in the format read by [`PlainText`](crate::parser::PlainText).
+///
+/// The title is followed by a blank line, then each stanza in order, separated by blank lines.
+/// Choruses and bridges are marked with `#c` and `#b`.
+pub fn plain_text(song: &Song) -> String {
+    let mut verse = 0;
+    let stanzas: Vec<String> = song
+        .stanzas()
+        .map(|(kind, text)| match kind {
+            StanzaType::Verse => {
+                // Put back any Bible verse numbers
+                let text: Vec<String> = text
+                    .lines()
+                    .enumerate()
+                    .map(|(i, line)| match song.verse_number(verse, i) {
+                        Some(n) => format!("{} {}", n, line),
+                        None => line.to_owned(),
+                    })
+                    .collect();
+                verse += 1;
+                stanza(&text.join("\n"))
+            }
+            StanzaType::Chorus => format!("#c\n{}", stanza(text)),
+            StanzaType::Bridge => format!("#b\n{}", stanza(text)),
+        })
+        .collect();
+
+    format!("{}\n\n{}\n", song.title.trim(), stanzas.join("\n\n"))
+}
+
+/// Drops blank lines, which would otherwise split the stanza when read back.
+fn stanza(text: &str) -> String {
+    text.lines()
+        .map(str::trim_end)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
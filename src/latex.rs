@@ -1,11 +1,16 @@
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
     io::{BufWriter, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Command, Output},
 };
 
-use crate::{config::Config, error::LaTeXError, Song};
+use crate::{
+    config::{Config, TitlePage, VerseLabels},
+    error::LaTeXError,
+    normalise, Song,
+};
 
 /// Represents a LaTeX package
 #[derive(Debug, Default)]
@@ -52,7 +57,25 @@ pub struct LaTeXBuilder<'a> {
     verse_fmt: String,
     chorus_fmt: String,
     bridge_fmt: String,
-    cover: String,
+    cover: Option<String>,
+    title_page: Option<String>,
+    columns: u8,
+    large_print: bool,
+    verse_labels: VerseLabels,
+    chorus_label: Option<String>,
+    bridge_label: Option<String>,
+    compact_choruses: bool,
+    bible_verse_numbers: bool,
+    keep_songs_together: bool,
+    contents: bool,
+    first_line_index: bool,
+    chorus_index: bool,
+    author_index: bool,
+    tune_index: bool,
+    scripture_index: bool,
+    acknowledgements: bool,
+    /// Print LORD in small caps
+    lord: bool,
     preamble_extra: Option<String>,
     songs: Vec<Song>,
 
@@ -67,21 +90,75 @@ impl<'a> LaTeX<'a> {
             source,
         })?;
 
+        // Large print is at least 17pt
+        let font_size = if config.large_print {
+            config.font_size.max(17)
+        } else {
+            config.font_size
+        };
+
         let builder = LaTeXBuilder {
             file,
             path,
-            doc_class: String::from("article"),
+            // The standard classes only go from 10pt to 12pt
+            doc_class: String::from(match font_size {
+                10..=12 => "article",
+                _ => "extarticle",
+            }),
             doc_opts: vec![
-                String::from("a4paper"),
+                String::from(config.paper.latex_option()),
+                format!("{}pt", font_size),
                 String::from("twoside"),
                 String::from("titlepage"),
             ],
             version: (1, 0, 0),
             packages: Vec::new(),
             verse_fmt: String::new(),
-            chorus_fmt: String::from(r"\quad\textit"),
-            bridge_fmt: String::from(r"\textit"),
-            cover: format!("\\includepdf{{./{}}}", &config.cover_image),
+            // Italics are harder to read in large print
+            chorus_fmt: String::from(if config.large_print {
+                r"\quad"
+            } else {
+                r"\quad\textit"
+            }),
+            bridge_fmt: String::from(if config.large_print { "" } else { r"\textit" }),
+            cover: config
+                .cover_image
+                .as_ref()
+                .map(|c| LaTeXBuilder::cover_page(c)),
+            title_page: config
+                .title_page
+                .as_ref()
+                .map(|t| LaTeXBuilder::title_page(t, &config.name)),
+            columns: if config.large_print {
+                1
+            } else {
+                config.columns
+            },
+            large_print: config.large_print,
+            // Large print labels every stanza, unless told otherwise
+            verse_labels: match config.verse_labels {
+                VerseLabels::None if config.large_print => VerseLabels::Words,
+                labels => labels,
+            },
+            chorus_label: config
+                .chorus_label
+                .clone()
+                .or_else(|| config.large_print.then(|| String::from("Chorus"))),
+            bridge_label: config
+                .bridge_label
+                .clone()
+                .or_else(|| config.large_print.then(|| String::from("Bridge"))),
+            compact_choruses: config.compact_choruses,
+            bible_verse_numbers: config.bible_verse_numbers,
+            keep_songs_together: config.keep_songs_together,
+            contents: config.contents,
+            first_line_index: config.first_line_index,
+            chorus_index: config.chorus_index,
+            author_index: config.author_index,
+            tune_index: config.tune_index,
+            scripture_index: config.scripture_index,
+            acknowledgements: config.acknowledgements,
+            lord: config.normalise.lord,
             preamble_extra: None,
             songs: Vec::<Song>::new(),
 
@@ -89,10 +166,18 @@ impl<'a> LaTeX<'a> {
         };
 
         // Add default packages
+        let margins = &config.margins;
         builder
-            .use_package_str(&["geometry", "left=1cm", "right=1cm", "top=1cm", "bottom=2cm"])?
+            .use_package_str(&[
+                "geometry",
+                &format!("left={}", margins.left),
+                &format!("right={}", margins.right),
+                &format!("top={}", margins.top),
+                &format!("bottom={}", margins.bottom),
+            ])?
+            // Must come before hyperref
+            .use_package_str(&["imakeidx"])?
             .use_package_str(&["hyperref", "hyperindex"])?
-            .use_package_str(&["makeidx"])?
             .use_package_str(&["pdfpages"])?
             .use_package_str(&["fancyhdr"])?
             .use_package_str(&["graphicx"])?
@@ -114,14 +199,76 @@ impl<'a> LaTeX<'a> {
             .map_err(LaTeXError::IOError)
     }
 
-    pub fn clean(&self) -> Result<Output, LaTeXError> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the PDF the LaTeX compiles to.
+    pub fn pdf_path(&self) -> PathBuf {
+        self.path.with_extension("pdf")
+    }
+
+    /// Path of the booklet imposed from the compiled PDF.
+    pub fn booklet_pdf_path(&self) -> PathBuf {
+        self.config.booklet_path()
+    }
+
+    /// Imposes the compiled PDF as a booklet for saddle-stitch printing, with pdfpages.
+    ///
+    /// The pages are padded with blanks to a multiple of four and placed two to a side of a
+    /// landscape sheet, in the order which reads correctly once the stack is folded.
+    pub fn impose(&self) -> Result<Output, LaTeXError> {
+        let path = self.booklet_pdf_path().with_extension("tex");
+        {
+            let file = File::create(&path).map_err(|source| LaTeXError::CreateFileError {
+                path: path.display().to_string(),
+                source,
+            })?;
+            let mut stream = BufWriter::new(file);
+            writeln!(stream, r"\documentclass{{article}}")?;
+            writeln!(
+                stream,
+                r"\usepackage[papersize={{{}}}, margin=0pt]{{geometry}}",
+                self.config.paper.sheet_size()
+            )?;
+            writeln!(stream, r"\usepackage{{pdfpages}}")?;
+            writeln!(stream)?;
+            writeln!(stream, r"\begin{{document}}")?;
+            writeln!(
+                stream,
+                "{}",
+                LaTeXBuilder::literal_path("songsheet", &self.pdf_path().display().to_string())
+            )?;
+            writeln!(stream, r"\includepdf[pages=-, booklet=true]{{\songsheet}}")?;
+            writeln!(stream, r"\end{{document}}")?;
+            stream.flush()?;
+        }
+
+        let output = Command::new(&self.config.latex_cmd)
+            .args(&self.config.latex_args)
+            .arg(&path)
+            .output()
+            .map_err(LaTeXError::IOError)?;
+        if !self.config.keep_tex_file {
+            fs::remove_file(&path)?;
+        }
+        Ok(output)
+    }
+
+    /// Removes the LaTeX file and the auxiliary files, unless they are to be kept.
+    pub fn clean(&self) -> Result<Option<Output>, LaTeXError> {
         if !self.config.keep_tex_file {
             fs::remove_file(&self.path)?;
         }
 
+        if self.config.keep_aux_files {
+            return Ok(None);
+        }
+
         Command::new(&self.config.latex_cmd)
             .arg("-c")
             .output()
+            .map(Some)
             .map_err(LaTeXError::IOError)
     }
 }
@@ -137,6 +284,14 @@ impl<'a> LaTeXBuilder<'a> {
             // Create buffered writer
             let mut stream = BufWriter::new(&self.file);
 
+            // Tag the PDF for screen readers, if this LaTeX can
+            if self.large_print {
+                writeln!(
+                    stream,
+                    r"\ifdefined\DocumentMetadata\DocumentMetadata{{lang=en, testphase=phase-III}}\fi"
+                )?;
+            }
+
             // Write document class
             writeln!(
                 stream,
@@ -164,17 +319,59 @@ impl<'a> LaTeXBuilder<'a> {
                 writeln!(stream, "{{{}}}", p.name)?;
             }
 
+            // Indices refer to song numbers rather than pages, if there are any
+            let numbered = self.songs.iter().any(|s| s.number.is_some());
+
+            // Only make the indices at the back if there is something to put in them
+            let author_index =
+                self.author_index && self.songs.iter().any(|s| !s.writers().is_empty());
+            let tune_index = self.tune_index && self.songs.iter().any(|s| s.tune.is_some());
+            let scripture_index =
+                self.scripture_index && self.songs.iter().any(|s| !s.references.is_empty());
+
             // Index
             writeln!(stream)?;
             writeln!(stream, "% ====   Index   ====")?;
             writeln!(stream, r"\makeindex")?;
+            if self.first_line_index {
+                writeln!(
+                    stream,
+                    r"\makeindex[name=firstlines, title=Index of First Lines]"
+                )?;
+            }
+            if self.chorus_index {
+                writeln!(
+                    stream,
+                    r"\makeindex[name=choruses, title=Index of Choruses]"
+                )?;
+            }
+            if author_index {
+                writeln!(
+                    stream,
+                    r"\makeindex[name=authors, title=Index of Authors and Composers]"
+                )?;
+            }
+            if tune_index {
+                writeln!(stream, r"\makeindex[name=tunes, title=Index of Tunes]")?;
+            }
+            if scripture_index {
+                writeln!(
+                    stream,
+                    r"\makeindex[name=scripture, title=Index of Scripture]"
+                )?;
+            }
 
             // Counters
             writeln!(stream)?;
             writeln!(stream, "% ====   Counters   ====")?;
             writeln!(stream, r"\newtotcounter{{songcount}}")?;
             writeln!(stream, r"\newtotcounter{{psalmcount}}")?;
-            writeln!(stream, r"\definecolor{{title dark}}{{HTML}}{{7E73A7}}")?;
+            // Black on white for the most contrast in large print
+            writeln!(
+                stream,
+                r"\definecolor{{title dark}}{{HTML}}{{{}}}",
+                if self.large_print { "000000" } else { "7E73A7" }
+            )?;
 
             // Footer
             writeln!(stream)?;
@@ -184,6 +381,13 @@ impl<'a> LaTeXBuilder<'a> {
             writeln!(stream, r"\cfoot{{{{\small\thepage}} \\ v{{\ssver}}}}")?;
             writeln!(stream, r"\renewcommand{{\headrulewidth}}{{0pt}}")?;
 
+            // Songs may only break between stanzas, unless kept together
+            let (line_end, stanza_end) = if self.keep_songs_together {
+                (r"\par", r"\vskip1em")
+            } else {
+                (r"\par\nopagebreak", r"\vskip1em\pagebreak[0]")
+            };
+
             // Expert LaTeX mode
             {
                 writeln!(stream)?;
@@ -199,10 +403,10 @@ impl<'a> LaTeXBuilder<'a> {
                 )?;
                 writeln!(
                     stream,
-                    r"\newcommand{{\@verseii}}[1]{{{}#1\par\@versei}}",
-                    self.verse_fmt
+                    r"\newcommand{{\@verseii}}[1]{{{}#1{}\@versei}}",
+                    self.verse_fmt, line_end
                 )?;
-                writeln!(stream, r"\newcommand{{\@verseend}}[1]{{\vskip1em}}")?;
+                writeln!(stream, r"\newcommand{{\@verseend}}[1]{{{}}}", stanza_end)?;
 
                 // Chorus
                 writeln!(stream)?;
@@ -214,10 +418,10 @@ impl<'a> LaTeXBuilder<'a> {
                 )?;
                 writeln!(
                     stream,
-                    r"\newcommand{{\@chorusii}}[1]{{{}{{#1}}\par\@chorusi}}",
-                    self.chorus_fmt
+                    r"\newcommand{{\@chorusii}}[1]{{{}{{#1}}{}\@chorusi}}",
+                    self.chorus_fmt, line_end
                 )?;
-                writeln!(stream, r"\newcommand{{\@chorusend}}[1]{{\vskip1em}}")?;
+                writeln!(stream, r"\newcommand{{\@chorusend}}[1]{{{}}}", stanza_end)?;
 
                 // Bridge
                 writeln!(stream)?;
@@ -229,10 +433,10 @@ impl<'a> LaTeXBuilder<'a> {
                 )?;
                 writeln!(
                     stream,
-                    r"\newcommand{{\@bridgeii}}[1]{{{}{{#1}}\par\@bridgei}}",
-                    self.bridge_fmt
+                    r"\newcommand{{\@bridgeii}}[1]{{{}{{#1}}{}\@bridgei}}",
+                    self.bridge_fmt, line_end
                 )?;
-                writeln!(stream, r"\newcommand{{\@bridgeend}}[1]{{\vskip1em}}")?;
+                writeln!(stream, r"\newcommand{{\@bridgeend}}[1]{{{}}}", stanza_end)?;
 
                 writeln!(stream)?;
                 writeln!(stream, r"\makeatother")?;
@@ -241,34 +445,80 @@ impl<'a> LaTeXBuilder<'a> {
             // Song environment
             writeln!(stream)?;
             writeln!(stream, "% ====   Song   ====")?;
-            writeln!(stream, r"\newenvironment{{song}}[1]%")?;
+            // Songs are indexed where they begin, as titles need escaping for makeindex
+            let (args, heading) = if numbered {
+                (2, r"\textbf{\large #1.\ #2}")
+            } else {
+                (1, r"\textbf{\large #1}")
+            };
+            writeln!(stream, r"\newenvironment{{song}}[{}]%", args)?;
             writeln!(stream, r"{{%")?;
-            writeln!(
-                stream,
-                r"    \begin{{minipage}}[t]{{0.94\columnwidth}}{{\stepcounter{{songcount}}\textbf{{\large #1}}\index{{#1}}}}%"
-            )?;
-            writeln!(stream, r"        \par\vspace{{2pt}}")?;
+            if self.keep_songs_together {
+                writeln!(
+                    stream,
+                    r"    \begin{{minipage}}[t]{{0.94\columnwidth}}{{\stepcounter{{songcount}}{}}}%",
+                    heading
+                )?;
+                writeln!(stream, r"        \par\vspace{{2pt}}")?;
+            } else {
+                writeln!(
+                    stream,
+                    r"    \par\noindent{{\stepcounter{{songcount}}{}}}%",
+                    heading
+                )?;
+                // Keep the title with the first stanza
+                writeln!(stream, r"        \par\nopagebreak\vspace{{2pt}}")?;
+            }
             writeln!(stream, r"}}%")?;
             writeln!(stream, r"{{%")?;
-            writeln!(stream, r"    \end{{minipage}}%")?;
+            if self.keep_songs_together {
+                writeln!(stream, r"    \end{{minipage}}%")?;
+            } else {
+                writeln!(stream, r"    \par%")?;
+            }
             writeln!(stream, r"    \vspace{{2em}}%")?;
             writeln!(stream, r"}}")?;
 
+            // Stanza labels
+            writeln!(
+                stream,
+                r"\newcommand{{\stanzalabel}}[1]{{{{\bfseries #1}}\par\nopagebreak}}"
+            )?;
+            writeln!(
+                stream,
+                r"\newcommand{{\versenumber}}[1]{{\makebox[1.5em][l]{{\bfseries #1.}}}}"
+            )?;
+            writeln!(
+                stream,
+                r"\newcommand{{\bibleverse}}[1]{{\llap{{\textsuperscript{{#1}}\,}}}}"
+            )?;
+            writeln!(
+                stream,
+                r"\newcommand{{\chorusrepeat}}[1]{{{}{{#1}}\par{}}}",
+                self.chorus_fmt, stanza_end
+            )?;
+
             // Psalm environment
             writeln!(stream)?;
             writeln!(stream, "% ====   Psalm   ====")?;
             writeln!(stream, r"\newenvironment{{psalm}}[2]%")?;
             writeln!(stream, r"{{%")?;
-            writeln!(stream, r"    \begin{{minipage}}[t]{{0.94\columnwidth}}%")?;
+            if self.keep_songs_together {
+                writeln!(stream, r"    \begin{{minipage}}[t]{{0.94\columnwidth}}%")?;
+            } else {
+                writeln!(stream, r"    \par%")?;
+            }
             writeln!(
                 stream,
-                r"        \begin{{center}}{{\stepcounter{{psalmcount}}\textbf{{\large #1}}\index{{#1}}{{\normalsize #2}}}}%"
+                r"        \begin{{center}}{{\stepcounter{{psalmcount}}\textbf{{\large #1}}{{\normalsize #2}}}}%"
             )?;
             writeln!(stream, r"            \par\vspace{{2pt}}")?;
             writeln!(stream, r"}}%")?;
             writeln!(stream, r"{{%")?;
             writeln!(stream, r"        \end{{center}}%")?;
-            writeln!(stream, r"    \end{{minipage}}%")?;
+            if self.keep_songs_together {
+                writeln!(stream, r"    \end{{minipage}}%")?;
+            }
             writeln!(stream, r"    \vspace{{2em}}%")?;
             writeln!(stream, r"}}%")?;
 
@@ -289,6 +539,11 @@ impl<'a> LaTeXBuilder<'a> {
             )?;
             writeln!(stream, r"\newcommand{{\LORD}}{{\textsc{{Lord}}}}")?;
             writeln!(stream, r"\newcommand{{\cp}}[1]{{{{\tiny\ttfamily#1}}}}")?;
+            writeln!(stream, r"\newcommand{{\songtotals}}{{{}}}", self.totals())?;
+            if numbered {
+                // Index entries show the song number in place of the page
+                writeln!(stream, r"\newcommand{{\songref}}[2]{{#1}}")?;
+            }
 
             // Preamble extras
             if let Some(p) = &self.preamble_extra {
@@ -303,59 +558,200 @@ impl<'a> LaTeXBuilder<'a> {
             writeln!(stream, r"\begin{{document}}")?;
             writeln!(stream, r"\sffamily")?;
             writeln!(stream)?;
-            writeln!(stream, r"\begin{{titlepage}}")?;
-            writeln!(stream, "{}", self.cover)?;
-            writeln!(stream, r"\end{{titlepage}}")?;
-            writeln!(stream)?;
-            writeln!(
-                stream,
-                r"\setcounter{{page}}{{2}}  % Make title page, page 1"
-            )?;
+            let front: Vec<&String> = [&self.cover, &self.title_page]
+                .into_iter()
+                .flatten()
+                .collect();
+            for page in &front {
+                writeln!(stream, r"\begin{{titlepage}}")?;
+                writeln!(stream, "{}", page)?;
+                writeln!(stream, r"\end{{titlepage}}")?;
+                writeln!(stream)?;
+            }
+            if !front.is_empty() {
+                writeln!(
+                    stream,
+                    r"\setcounter{{page}}{{{}}}  % Number pages after the front pages",
+                    front.len() + 1
+                )?;
+            }
+            if self.contents {
+                self.write_contents(&mut stream)?;
+            }
             writeln!(stream, r"\printindex")?;
-            writeln!(stream, r"\begin{{multicols}}{{2}}")?;
-            writeln!(stream, r"\raggedcolumns{{}}")?;
+            if self.first_line_index {
+                writeln!(stream, r"\printindex[firstlines]")?;
+            }
+            if self.chorus_index {
+                writeln!(stream, r"\printindex[choruses]")?;
+            }
+            // With a contents page, each section is headed and opens its own columns
+            let headed = self.contents && !self.songs.is_empty();
+            if !headed {
+                self.begin_columns(&mut stream)?;
+            }
 
             // Body
-            for s in &self.songs {
+            let mut section = 0;
+            for (i, s) in self.songs.iter().enumerate() {
+                if headed && (i == 0 || s.section != self.songs[i - 1].section) {
+                    if i > 0 {
+                        writeln!(stream, r"\label{{section-{}-end}}", section - 1)?;
+                        self.end_columns(&mut stream)?;
+                    }
+                    writeln!(stream)?;
+                    writeln!(
+                        stream,
+                        r"\section*{{{}}}\label{{section-{}}}",
+                        Self::safe(s.section.as_deref().unwrap_or_default()),
+                        section
+                    )?;
+                    self.begin_columns(&mut stream)?;
+                    section += 1;
+                }
+
                 writeln!(stream)?;
                 writeln!(stream, "% ====   {}   ====", s.title)?;
-                writeln!(stream, r"\begin{{song}}{{{}}}", Self::safe(&s.title))?;
+                let encap = match s.number.filter(|_| numbered) {
+                    Some(n) => {
+                        writeln!(
+                            stream,
+                            r"\begin{{song}}{{{}}}{{{}}}",
+                            n,
+                            self.lyric(&s.title)
+                        )?;
+                        format!("|songref{{{}}}", n)
+                    }
+                    None => {
+                        writeln!(stream, r"\begin{{song}}{{{}}}", self.lyric(&s.title))?;
+                        String::new()
+                    }
+                };
+                writeln!(
+                    stream,
+                    r"    \index{{{}{}}}",
+                    self.title_entry(&s.title),
+                    encap
+                )?;
+                if let Some(line) = s.first_line().filter(|_| self.first_line_index) {
+                    writeln!(
+                        stream,
+                        r"    \index[firstlines]{{{}{}}}",
+                        self.first_line_entry(line, &s.title),
+                        encap
+                    )?;
+                }
+                if let Some(line) = s.chorus_first_line().filter(|_| self.chorus_index) {
+                    writeln!(
+                        stream,
+                        r"    \index[choruses]{{{}{}}}",
+                        self.first_line_entry(line, &s.title),
+                        encap
+                    )?;
+                }
+                for writer in s.writers().iter().filter(|_| author_index) {
+                    writeln!(
+                        stream,
+                        r"    \index[authors]{{{}@{}!{}{}}}",
+                        Self::index_text(&Self::surname_first(writer)),
+                        Self::index_text(writer),
+                        self.title_entry(&s.title),
+                        encap
+                    )?;
+                }
+                if let Some(tune) = s.tune.as_ref().filter(|_| tune_index) {
+                    writeln!(
+                        stream,
+                        r"    \index[tunes]{{{}!{}{}}}",
+                        Self::index_text(tune),
+                        self.title_entry(&s.title),
+                        encap
+                    )?;
+                }
+                for r in s.references.iter().filter(|_| scripture_index) {
+                    // Sort by position in the Bible rather than alphabetically
+                    writeln!(
+                        stream,
+                        r"    \index[scripture]{{{:02}@{}!{:03}{:03}{}@{} \textit{{{}}}{}}}",
+                        r.book,
+                        r.book_name(),
+                        r.start.chapter,
+                        r.start.verse.unwrap_or_default(),
+                        r.start.part.map(String::from).unwrap_or_default(),
+                        r.passage().replace('-', "--"),
+                        self.title_entry(&s.title),
+                        encap
+                    )?;
+                }
                 // Write verses, chorus and brides
                 // Construction of the song checks order is valid
                 let mut cur_verse: usize = 0;
+                let mut chorus_written = false;
                 for c in s.order.chars() {
                     match c {
                         'v' => {
                             // Write current verse
+                            if self.verse_labels == VerseLabels::Words {
+                                writeln!(
+                                    stream,
+                                    r"    \stanzalabel{{Verse {}}}",
+                                    Self::number_word(cur_verse + 1)
+                                )?;
+                            }
                             writeln!(stream, r"    \verse")?;
-                            for line in s
-                                .verses
-                                .get(cur_verse)
-                                .ok_or(LaTeXError::VerseOutOfBounds {
-                                    index: cur_verse,
-                                    size: s.verses.len(),
-                                })?
-                                .lines()
-                            {
-                                writeln!(stream, "    {{{}}}", Self::safe(line))?;
+                            let verse =
+                                s.verses
+                                    .get(cur_verse)
+                                    .ok_or(LaTeXError::VerseOutOfBounds {
+                                        index: cur_verse,
+                                        size: s.verses.len(),
+                                    })?;
+                            for (i, line) in verse.lines().enumerate() {
+                                // Numbers go at the start of the first line
+                                let mut number = match s.verse_number(cur_verse, i) {
+                                    Some(n) if self.bible_verse_numbers => {
+                                        format!(r"\bibleverse{{{}}}", n)
+                                    }
+                                    _ => String::new(),
+                                };
+                                if self.verse_labels == VerseLabels::Numbers && i == 0 {
+                                    number.push_str(&format!(r"\versenumber{{{}}}", cur_verse + 1));
+                                }
+                                writeln!(stream, "    {{{}{}}}", number, self.lyric(line))?;
                             }
                             writeln!(stream, r"    \end")?;
                             cur_verse += 1;
                         }
                         'c' => {
                             if let Some(chorus) = &s.chorus {
+                                if self.compact_choruses && chorus_written {
+                                    let label = self.chorus_label.as_deref().unwrap_or("Chorus");
+                                    writeln!(
+                                        stream,
+                                        r"    \chorusrepeat{{{} (repeat)}}",
+                                        Self::safe(label)
+                                    )?;
+                                    continue;
+                                }
+                                chorus_written = true;
+                                if let Some(label) = &self.chorus_label {
+                                    writeln!(stream, r"    \stanzalabel{{{}}}", Self::safe(label))?;
+                                }
                                 writeln!(stream, r"    \chorus")?;
                                 for line in chorus.lines() {
-                                    writeln!(stream, "    {{{}}}", Self::safe(line))?;
+                                    writeln!(stream, "    {{{}}}", self.lyric(line))?;
                                 }
                                 writeln!(stream, r"    \end")?;
                             }
                         }
                         'b' => {
                             if let Some(bridge) = &s.bridge {
+                                if let Some(label) = &self.bridge_label {
+                                    writeln!(stream, r"    \stanzalabel{{{}}}", Self::safe(label))?;
+                                }
                                 writeln!(stream, r"    \bridge")?;
                                 for line in bridge.lines() {
-                                    writeln!(stream, "    {{{}}}", Self::safe(line))?;
+                                    writeln!(stream, "    {{{}}}", self.lyric(line))?;
                                 }
                                 writeln!(stream, r"    \end")?;
                             }
@@ -368,7 +764,25 @@ impl<'a> LaTeXBuilder<'a> {
 
             // Finish up
             writeln!(stream)?;
-            writeln!(stream, r"\end{{multicols}}")?;
+            if headed {
+                writeln!(stream, r"\label{{section-{}-end}}", section - 1)?;
+            }
+            self.end_columns(&mut stream)?;
+
+            // Back matter
+            if author_index {
+                writeln!(stream, r"\printindex[authors]")?;
+            }
+            if tune_index {
+                writeln!(stream, r"\printindex[tunes]")?;
+            }
+            if scripture_index {
+                writeln!(stream, r"\printindex[scripture]")?;
+            }
+            if self.acknowledgements {
+                self.write_acknowledgements(&mut stream)?;
+            }
+
             writeln!(stream, r"\end{{document}}")?;
 
             // Flush!
@@ -382,6 +796,115 @@ impl<'a> LaTeXBuilder<'a> {
         })
     }
 
+    /// Starts setting songs in columns, unless there is only one.
+    fn begin_columns<W>(&self, stream: &mut W) -> Result<(), LaTeXError>
+    where
+        W: Write,
+    {
+        if self.columns > 1 {
+            writeln!(stream, r"\begin{{multicols}}{{{}}}", self.columns)?;
+            writeln!(stream, r"\raggedcolumns{{}}")?;
+        }
+        Ok(())
+    }
+
+    fn end_columns<W>(&self, stream: &mut W) -> Result<(), LaTeXError>
+    where
+        W: Write,
+    {
+        if self.columns > 1 {
+            writeln!(stream, r"\end{{multicols}}")?;
+        }
+        Ok(())
+    }
+
+    /// Writes a page listing each section with its song count and pages.
+    fn write_contents<W>(&self, stream: &mut W) -> Result<(), LaTeXError>
+    where
+        W: Write,
+    {
+        // Consecutive songs from the same source make up a section
+        let mut sections: Vec<(&str, usize)> = Vec::new();
+        for s in &self.songs {
+            let name = s.section.as_deref().unwrap_or_default();
+            match sections.last_mut() {
+                Some((n, count)) if *n == name => *count += 1,
+                _ => sections.push((name, 1)),
+            }
+        }
+
+        writeln!(stream, "% ====   Contents   ====")?;
+        writeln!(stream, r"\section*{{Contents}}")?;
+        for (i, (name, count)) in sections.iter().enumerate() {
+            writeln!(
+                stream,
+                r"{} \dotfill {} \quad \pageref{{section-{}}}--\pageref{{section-{}-end}}\par",
+                Self::safe(name),
+                Self::count(*count, "song"),
+                i,
+                i
+            )?;
+        }
+        writeln!(stream, r"\clearpage")?;
+        writeln!(stream)?;
+        Ok(())
+    }
+
+    /// How many songs and psalms there are, e.g. "312 songs, 150 psalms".
+    fn totals(&self) -> String {
+        let psalms = self.songs.iter().filter(|s| s.is_psalm()).count();
+        let songs = self.songs.len() - psalms;
+        match (songs, psalms) {
+            (songs, 0) => Self::count(songs, "song"),
+            (0, psalms) => Self::count(psalms, "psalm"),
+            (songs, psalms) => format!(
+                "{}, {}",
+                Self::count(songs, "song"),
+                Self::count(psalms, "psalm")
+            ),
+        }
+    }
+
+    fn count(n: usize, noun: &str) -> String {
+        match n {
+            1 => format!("1 {}", noun),
+            n => format!("{} {}s", n, noun),
+        }
+    }
+
+    /// Writes a page acknowledging the copyright of each song, grouped by rights holder.
+    fn write_acknowledgements<W>(&self, stream: &mut W) -> Result<(), LaTeXError>
+    where
+        W: Write,
+    {
+        let mut holders: BTreeMap<String, Vec<&Song>> = BTreeMap::new();
+        for s in &self.songs {
+            if let Some(holder) = s.rights_holder() {
+                holders.entry(holder).or_default().push(s);
+            }
+        }
+        if holders.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(stream)?;
+        writeln!(stream, "% ====   Acknowledgements   ====")?;
+        writeln!(stream, r"\clearpage")?;
+        writeln!(stream, r"\section*{{Acknowledgements}}")?;
+        for (holder, songs) in holders {
+            writeln!(stream, r"\subsection*{{{}}}", Self::safe(holder))?;
+            for s in songs {
+                writeln!(
+                    stream,
+                    r"\textit{{{}}} \hfill {{\small {}}}\par",
+                    self.lyric(&s.title),
+                    Self::safe(s.copyright.as_deref().unwrap_or_default())
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn use_package(mut self, pkg: Package) -> Self {
         self.packages.push(pkg);
         self
@@ -423,11 +946,91 @@ impl<'a> LaTeXBuilder<'a> {
         self
     }
 
-    pub fn set_cover(mut self, cover: String) -> Self {
+    pub fn set_cover(mut self, cover: Option<String>) -> Self {
         self.cover = cover;
         self
     }
 
+    pub fn set_title_page(mut self, title_page: Option<String>) -> Self {
+        self.title_page = title_page;
+        self
+    }
+
+    pub fn set_columns(mut self, columns: u8) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn set_large_print(mut self, large_print: bool) -> Self {
+        self.large_print = large_print;
+        self
+    }
+
+    pub fn set_verse_labels(mut self, verse_labels: VerseLabels) -> Self {
+        self.verse_labels = verse_labels;
+        self
+    }
+
+    pub fn set_chorus_label(mut self, chorus_label: Option<String>) -> Self {
+        self.chorus_label = chorus_label;
+        self
+    }
+
+    pub fn set_bridge_label(mut self, bridge_label: Option<String>) -> Self {
+        self.bridge_label = bridge_label;
+        self
+    }
+
+    pub fn set_compact_choruses(mut self, compact_choruses: bool) -> Self {
+        self.compact_choruses = compact_choruses;
+        self
+    }
+
+    pub fn set_bible_verse_numbers(mut self, bible_verse_numbers: bool) -> Self {
+        self.bible_verse_numbers = bible_verse_numbers;
+        self
+    }
+
+    pub fn set_keep_songs_together(mut self, keep_songs_together: bool) -> Self {
+        self.keep_songs_together = keep_songs_together;
+        self
+    }
+
+    pub fn set_contents(mut self, contents: bool) -> Self {
+        self.contents = contents;
+        self
+    }
+
+    pub fn set_first_line_index(mut self, first_line_index: bool) -> Self {
+        self.first_line_index = first_line_index;
+        self
+    }
+
+    pub fn set_chorus_index(mut self, chorus_index: bool) -> Self {
+        self.chorus_index = chorus_index;
+        self
+    }
+
+    pub fn set_author_index(mut self, author_index: bool) -> Self {
+        self.author_index = author_index;
+        self
+    }
+
+    pub fn set_tune_index(mut self, tune_index: bool) -> Self {
+        self.tune_index = tune_index;
+        self
+    }
+
+    pub fn set_scripture_index(mut self, scripture_index: bool) -> Self {
+        self.scripture_index = scripture_index;
+        self
+    }
+
+    pub fn set_acknowledgements(mut self, acknowledgements: bool) -> Self {
+        self.acknowledgements = acknowledgements;
+        self
+    }
+
     pub fn set_preamble_extra(mut self, preamble_extra: Option<String>) -> Self {
         self.preamble_extra = preamble_extra;
         self
@@ -438,17 +1041,256 @@ impl<'a> LaTeXBuilder<'a> {
         self
     }
 
+    /// Spells out a verse number, e.g. Two, falling back to digits past twenty.
+    fn number_word(n: usize) -> String {
+        const WORDS: [&str; 20] = [
+            "One",
+            "Two",
+            "Three",
+            "Four",
+            "Five",
+            "Six",
+            "Seven",
+            "Eight",
+            "Nine",
+            "Ten",
+            "Eleven",
+            "Twelve",
+            "Thirteen",
+            "Fourteen",
+            "Fifteen",
+            "Sixteen",
+            "Seventeen",
+            "Eighteen",
+            "Nineteen",
+            "Twenty",
+        ];
+        match n.checked_sub(1).and_then(|i| WORDS.get(i)) {
+            Some(word) => word.to_string(),
+            None => n.to_string(),
+        }
+    }
+
+    /// Lays out a title page from its fields, using `name` if it has no title.
+    fn title_page(fields: &TitlePage, name: &str) -> String {
+        let title = fields.title.as_deref().unwrap_or(name);
+        let mut page = vec![
+            String::from(r"\centering"),
+            String::from(r"\vspace*{0.25\textheight}"),
+            format!(
+                r"{{\Huge\bfseries\color{{title dark}} {}\par}}",
+                Self::safe(title)
+            ),
+        ];
+        if let Some(subtitle) = &fields.subtitle {
+            page.push(String::from(r"\vspace{1em}"));
+            page.push(format!(r"{{\Large {}\par}}", Self::safe(subtitle)));
+        }
+        page.push(String::from(r"\vfill"));
+        if let Some(church) = &fields.church {
+            page.push(format!(r"{{\Large {}\par}}", Self::safe(church)));
+            page.push(String::from(r"\vspace{0.5em}"));
+        }
+        if let Some(date) = &fields.date {
+            page.push(format!(r"{{\large {}\par}}", Self::safe(date)));
+        }
+        if let Some(version) = &fields.version {
+            page.push(format!(r"{{\small Version {}\par}}", Self::safe(version)));
+        }
+        if fields.totals {
+            // Defined once the songs are known
+            page.push(String::from(r"\vspace{0.5em}"));
+            page.push(String::from(r"{\small\songtotals\par}"));
+        }
+        page.join("\n")
+    }
+
+    /// Index entry for a first line, in italics unless it is just the title.
+    ///
+    /// Sorted by the plain text, so "The \LORD{}'s my shepherd" sorts as "The LORD's my shepherd".
+    fn first_line_entry(&self, line: &str, title: &str) -> String {
+        let simplify = |s: &str| -> String {
+            s.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+
+        if simplify(line) == simplify(title) {
+            self.title_entry(line)
+        } else {
+            let entry = Self::index_text(line);
+            format!(r"{}@\textit{{{}}}", entry, self.small_caps(&entry))
+        }
+    }
+
+    /// Index entry for a title, sorted by the plain text.
+    fn title_entry(&self, title: &str) -> String {
+        let entry = Self::index_text(title);
+        let shown = self.small_caps(&entry);
+        if shown == entry {
+            entry
+        } else {
+            format!("{}@{}", entry, shown)
+        }
+    }
+
+    /// A line of a stanza, or a title, ready to write.
+    fn lyric(&self, line: &str) -> String {
+        self.small_caps(&Self::safe(line))
+    }
+
+    /// Prints LORD in small caps in escaped text, if wanted.
+    fn small_caps(&self, text: &str) -> String {
+        if self.lord {
+            normalise::small_caps_lord(text, r"\LORD{}")
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// Cover page showing the image or PDF at `path`, as given in the config.
+    fn cover_page(path: &str) -> String {
+        format!(
+            "{}\n\\includepdf{{\\coverimage}}",
+            Self::literal_path("coverimage", path)
+        )
+    }
+
+    /// Defines `\{command}` as `path`, read with LaTeX's special characters as plain ones, and
+    /// quoted for spaces.
+    fn literal_path(command: &str, path: &str) -> String {
+        [
+            String::from(r"\begingroup"),
+            String::from(
+                r"\catcode`\%=12 \catcode`\#=12 \catcode`\&=12 \catcode`\$=12 \catcode`\^=12 \catcode`\_=12 \catcode`\~=12",
+            ),
+            format!(r#"\gdef\{}{{"{}"}}"#, command, path),
+            String::from(r"\endgroup"),
+        ]
+        .join("\n")
+    }
+
+    /// Sort key for a writer in the author index, e.g. "Monk, William Henry", or the only name.
+    fn surname_first(writer: &str) -> String {
+        match writer.rsplit_once(' ') {
+            Some((rest, surname)) => format!("{}, {}", surname, rest),
+            None => writer.to_owned(),
+        }
+    }
+
+    /// Escapes `text` for use in an index entry, quoting makeindex's special characters.
+    fn index_text(text: &str) -> String {
+        let mut entry = String::new();
+        for c in Self::safe(text).chars() {
+            if matches!(c, '"' | '!' | '@' | '|') {
+                entry.push('"');
+            }
+            entry.push(c);
+        }
+        entry
+    }
+
     fn safe<T>(string: T) -> String
     where
         T: AsRef<str>,
     {
-        string
-            .as_ref()
-            .replace('%', r#"\%"#)
-            .replace('$', r#"\$"#)
-            .replace('{', r#"\{"#)
-            .replace('}', r#"\}"#)
-            .replace('#', r#"\#"#)
-            .replace('&', r#"\&"#)
+        let mut safe = String::new();
+        for c in string.as_ref().chars() {
+            match c {
+                '%' | '$' | '{' | '}' | '#' | '&' | '_' => {
+                    safe.push('\\');
+                    safe.push(c);
+                }
+                '\\' => safe.push_str(r"\textbackslash{}"),
+                '^' => safe.push_str(r"\^{}"),
+                '~' => safe.push_str(r"\~{}"),
+                c if c.is_control() && !matches!(c, '\n' | '\t') => {}
+                c => safe.push(c),
+            }
+        }
+        safe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lord_in_small_caps_in_titles() {
+        let dir = std::env::temp_dir().join(format!("song_sheet_lord_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let name = dir.join("sheet");
+        let config: Config =
+            toml::from_str(&format!("name = {:?}", name.display().to_string())).unwrap();
+        let song = Song::builder("Psalm 23 (The LORD's my Shepherd)")
+            .add_verse("The LORD's my shepherd, I'll not want")
+            .set_order("v")
+            .build()
+            .unwrap();
+
+        LaTeX::builder_default(&config)
+            .unwrap()
+            .add_song(song)
+            .write_to_file()
+            .unwrap();
+        let tex = fs::read_to_string(name.with_extension("tex")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(tex.contains(r"{Psalm 23 (The \LORD{}'s my Shepherd)}"));
+        assert!(tex.contains(
+            r"\index{Psalm 23 (The LORD's my Shepherd)@Psalm 23 (The \LORD{}'s my Shepherd)"
+        ));
+        assert!(tex.contains(r"{The \LORD{}'s my shepherd, I'll not want}"));
+    }
+
+    #[test]
+    fn a5_booklet_on_a4() {
+        let dir = std::env::temp_dir().join(format!("song_sheet_booklet_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let name = dir.join("Song Sheet");
+        let config: Config = toml::from_str(&format!(
+            "name = {:?}\npaper = \"a5\"\nlatex_cmd = \"true\"\nkeep_tex_file = true",
+            name.display().to_string()
+        ))
+        .unwrap();
+        let latex = LaTeX {
+            path: name.with_extension("tex"),
+            config: &config,
+        };
+
+        latex.impose().unwrap();
+        let tex = fs::read_to_string(dir.join("Song Sheet-booklet.tex")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(tex.contains(r"\usepackage[papersize={297mm,210mm}, margin=0pt]{geometry}"));
+        assert!(tex.contains(r"\includepdf[pages=-, booklet=true]{\songsheet}"));
+    }
+
+    #[test]
+    fn safe_escapes_specials() {
+        assert_eq!(
+            LaTeXBuilder::safe(r"a\b {c} 50% $1 #2 & x_y ^ ~"),
+            r"a\textbackslash{}b \{c\} 50\% \$1 \#2 \& x\_y \^{} \~{}"
+        );
+        assert_eq!(LaTeXBuilder::safe("a\u{b}b"), "ab");
+    }
+
+    #[test]
+    fn cover_path_as_given() {
+        let cover = LaTeXBuilder::cover_page("/home/me/Song Sheets/cover_2%.pdf");
+        assert!(cover.contains(r#"\gdef\coverimage{"/home/me/Song Sheets/cover_2%.pdf"}"#));
+        assert!(cover.contains(r"\catcode`\%=12"));
+        assert!(cover.ends_with(r"\includepdf{\coverimage}"));
+    }
+
+    #[test]
+    fn surnames_sort_first() {
+        assert_eq!(
+            LaTeXBuilder::surname_first("William Henry Monk"),
+            "Monk, William Henry"
+        );
+        assert_eq!(LaTeXBuilder::surname_first("Mendelssohn"), "Mendelssohn");
     }
 }
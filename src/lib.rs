@@ -1,57 +1,365 @@
-use std::fs;
+use std::{
+    collections::HashSet,
+    fs, panic,
+    path::{Path, PathBuf},
+    thread,
+};
 
+use cache::Cache;
 use config::Config;
-use error::{ParseError, SongSheetError};
-use log::{info, trace};
+use error::{ExportError, LaTeXError, ParseError, SongError, SongSheetError};
+use exporter::ExporterType;
+use log::{info, trace, warn};
+use parser::{Entry, ParserType, PlainText};
 
-use crate::{latex::LaTeX, parser::ParserType::*, song::Song};
+use crate::{latex::LaTeX, song::Song};
 
+mod cache;
 pub mod config;
 pub mod error;
+pub mod exporter;
 pub mod latex;
+pub mod lint;
+mod normalise;
+mod numbering;
 pub mod parser;
+pub mod scripture;
 pub mod song;
+pub mod watch;
 
-pub fn run(config: &Config) -> Result<(), SongSheetError> {
-    let mut latex_builder = LaTeX::builder_default(config)?;
+/// Represents a song with a problem which was worked around in lenient mode
+#[derive(Debug)]
+struct Mended {
+    title: String,
+    error: SongError,
+    /// Whether the song was left out, rather than repaired
+    skipped: bool,
+}
 
-    let songs = parse_source(config)?;
-    for song in songs {
-        trace!("Adding {} to latex.", song.title);
-        latex_builder = latex_builder.add_song(song);
+pub fn run(config: &Config) -> Result<(), SongSheetError> {
+    let mut unchanged = true;
+    for edition in config.editions() {
+        let cache = Cache::new(&edition)?;
+        let previous = Cache::load(&edition).unwrap_or_default();
+        unchanged &= edition.incremental
+            && cache.same_inputs(&previous)
+            && edition.pdf_path().exists()
+            && (!edition.booklet || edition.booklet_path().exists());
+    }
+    if unchanged {
+        info!("Nothing has changed since the last build.");
+        return Ok(());
     }
 
-    info!("Writing to LaTeX file.");
-    let latex = latex_builder.write_to_file()?;
+    let (songs, mended) = parse_source(config)?;
+    build(config, songs)?;
+    report_mended(&mended);
+
+    info!("Done!");
+    Ok(())
+}
+
+/// Numbers `songs`, then writes, compiles and cleans up their LaTeX for every edition.
+///
+/// The editions are compiled in parallel. In incremental mode, compiling is skipped if the LaTeX
+/// is the same as last time.
+fn build(config: &Config, mut songs: Vec<Song>) -> Result<(), SongSheetError> {
+    // Number once, so every edition agrees
+    numbering::number_songs(config, &mut songs)?;
+
+    let editions = config.editions();
+    let mut written = Vec::new();
+    for edition in &editions {
+        let mut latex_builder = LaTeX::builder_default(edition)?;
+        for song in &songs {
+            trace!("Adding {} to {}.", song.title, edition.name);
+            latex_builder = latex_builder.add_song(song.clone());
+        }
+
+        info!("Writing {}.", edition.name);
+        let latex = latex_builder.write_to_file()?;
 
-    info!("Compiling LaTeX.");
-    latex.compile()?;
+        let cache = Cache::new(edition)?.with_tex(latex.path())?;
+        let unchanged = edition.incremental
+            && Cache::load(edition).as_ref() == Some(&cache)
+            && latex.pdf_path().exists()
+            && (!edition.booklet || latex.booklet_pdf_path().exists());
+        if unchanged {
+            info!("{} has not changed, skipping compilation.", edition.name);
+        }
+        written.push((edition, latex, cache, unchanged));
+    }
+
+    thread::scope(|s| {
+        let compiling: Vec<_> = written
+            .iter()
+            .filter(|(_, _, _, unchanged)| !unchanged)
+            .map(|(edition, latex, cache, _)| {
+                s.spawn(move || -> Result<(), LaTeXError> {
+                    info!("Compiling {}.", edition.name);
+                    let mut output = latex.compile()?;
+                    if output.status.success() && edition.booklet {
+                        info!("Imposing {} as a booklet.", edition.name);
+                        output = latex.impose()?;
+                    }
+                    if output.status.success() {
+                        cache.save(edition);
+                    } else {
+                        warn!(
+                            "{} exited with {} for {}.",
+                            edition.latex_cmd, output.status, edition.name
+                        );
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        compiling
+            .into_iter()
+            .try_for_each(|h| h.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+    })?;
 
     info!("Cleaning up LaTeX files.");
-    latex.clean()?;
+    for (_, latex, _, _) in &written {
+        latex.clean()?;
+    }
 
-    info!("Done!");
     Ok(())
 }
 
-fn parse_source(config: &Config) -> Result<Vec<Song>, ParseError> {
-    match &config.from {
-        PlainText => Err(ParseError::NotAvailable(PlainText)),
-        VideoPsalm => video_psalm_songs(config),
-        Unknown(s) => Err(ParseError::UnknownParser(s.clone())),
+/// Logs the songs which were repaired or skipped in lenient mode.
+fn report_mended(mended: &[Mended]) {
+    if mended.is_empty() {
+        return;
+    }
+
+    let skipped = mended.iter().filter(|m| m.skipped).count();
+    warn!(
+        "{} songs were skipped and {} repaired:",
+        skipped,
+        mended.len() - skipped
+    );
+    for m in mended {
+        let action = if m.skipped { "Skipped" } else { "Repaired" };
+        warn!("  {} {}: {}", action, m.title, m.error);
     }
 }
 
-fn video_psalm_songs(config: &Config) -> Result<Vec<Song>, ParseError> {
-    trace!("Reading {} to string.", &config.source);
-    let buf = fs::read_to_string(&config.source).map_err(|source| ParseError::ReadError {
-        path: config.name.clone(),
+/// Converts the songs in `input` to another format.
+///
+/// A single song is written to `output`. If `output` is a directory (or ends with a separator), or
+/// `input` holds more than one song, each song is written to its own file in `output`.
+pub fn convert<P, Q>(
+    input: &P,
+    from: &ParserType,
+    output: &Q,
+    to: &ExporterType,
+) -> Result<(), SongSheetError>
+where
+    P: AsRef<Path> + ?Sized,
+    Q: AsRef<Path> + ?Sized,
+{
+    let output = output.as_ref();
+    let songs = parse_file(input, from)?;
+
+    if songs.is_empty() {
+        warn!("No songs found in {}.", input.as_ref().display());
+        return Ok(());
+    }
+
+    let to_dir = output.is_dir() || output.as_os_str().to_string_lossy().ends_with(['/', '\\']);
+    if songs.len() == 1 && !to_dir {
+        info!("Writing {}.", output.display());
+        return write_file(output, &to.export(&songs[0])).map_err(SongSheetError::from);
+    }
+
+    fs::create_dir_all(output).map_err(|source| ExportError::WriteError {
+        path: output.display().to_string(),
         source,
     })?;
+    info!("Writing {} songs to {}.", songs.len(), output.display());
+    let mut used = HashSet::new();
+    for song in &songs {
+        // Songs can share a title, don't let them overwrite each other
+        let stem = exporter::file_stem(&song.title);
+        let mut name = format!("{}.{}", stem, to.extension());
+        let mut n = 1;
+        while !used.insert(name.to_lowercase()) {
+            n += 1;
+            name = format!("{} ({}).{}", stem, n, to.extension());
+        }
+        let path = output.join(name);
+        trace!("Writing {}.", path.display());
+        write_file(&path, &to.export(song))?;
+    }
+
+    Ok(())
+}
+
+/// Parses every song in `path` using the given parser, failing on the first invalid song.
+///
+/// Plain text songs are one per file, so `path` may also be a directory of them.
+pub fn parse_file<P>(path: &P, from: &ParserType) -> Result<Vec<Song>, ParseError>
+where
+    P: AsRef<Path> + ?Sized,
+{
+    parse_entries(path, from)?
+        .into_iter()
+        .map(|e| e.builder.build().map_err(ParseError::from))
+        .collect()
+}
 
-    info!("Parsing {}.", &config.source);
-    Ok(parser::video_psalm(&buf.trim_start_matches('\u{feff}'))?
+/// Parses every song in `path` using the given parser, keeping invalid songs.
+pub fn parse_entries<P>(path: &P, from: &ParserType) -> Result<Vec<Entry>, ParseError>
+where
+    P: AsRef<Path> + ?Sized,
+{
+    let path = path.as_ref();
+    let entries = match from {
+        ParserType::PlainText => plain_text_entries(path)?,
+        ParserType::VideoPsalm => {
+            info!("Parsing {}.", path.display());
+            parser::video_psalm_entries(&path.display().to_string(), &read_source(path)?)?
+        }
+    };
+
+    let section = section_name(path, from);
+    Ok(entries
         .into_iter()
-        .filter(|s| !config.exclude.contains(&s.title))
+        .map(|mut e| {
+            e.builder = e.builder.set_section(&section);
+            e
+        })
         .collect())
 }
+
+/// Names the section of the song sheet for a source: the song book, or the directory of plain
+/// text songs.
+fn section_name(path: &Path, from: &ParserType) -> String {
+    let path = match from {
+        ParserType::PlainText if path.is_file() => path.parent().unwrap_or(path),
+        _ => path,
+    };
+    path.file_stem()
+        .or_else(|| path.file_name())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Parses every configured source.
+fn parse_source(config: &Config) -> Result<(Vec<Song>, Vec<Mended>), ParseError> {
+    let mut entries = Vec::new();
+    for source in &config.source {
+        entries.append(&mut parse_entries(source, &config.from)?);
+    }
+    collect_songs(config, entries)
+}
+
+/// Builds the songs which are not excluded, and normalises their text.
+///
+/// In lenient mode invalid songs are repaired or skipped, and the problems returned, rather than
+/// failing the whole song sheet.
+fn collect_songs(
+    config: &Config,
+    entries: Vec<Entry>,
+) -> Result<(Vec<Song>, Vec<Mended>), ParseError> {
+    let mut songs = Vec::new();
+    let mut mended = Vec::new();
+    for entry in entries {
+        if config.exclude.contains(&entry.title) {
+            continue;
+        }
+
+        // Repairs never change which stanzas are unused, so these hold in lenient mode too
+        let warnings = entry.builder.warnings();
+
+        if !config.lenient {
+            let mut song = entry.builder.build()?;
+            warnings.iter().for_each(|w| warn!("{}", w));
+            normalise::normalise(&config.normalise, &mut song);
+            songs.push(song);
+            continue;
+        }
+
+        match entry.builder.build_lenient() {
+            Ok((mut song, errors)) => {
+                warnings.iter().for_each(|w| warn!("{}", w));
+                normalise::normalise(&config.normalise, &mut song);
+                mended.extend(errors.into_iter().map(|error| Mended {
+                    title: entry.title.clone(),
+                    error,
+                    skipped: false,
+                }));
+                songs.push(song);
+            }
+            Err(error) => {
+                mended.push(Mended {
+                    title: entry.title,
+                    error,
+                    skipped: true,
+                });
+            }
+        }
+    }
+    Ok((songs, mended))
+}
+
+/// The files which make up a source: plain text directories hold one song per file.
+fn source_files(path: &Path, from: &ParserType) -> Result<Vec<PathBuf>, ParseError> {
+    if !matches!(from, ParserType::PlainText) || !path.is_dir() {
+        return Ok(vec![path.to_owned()]);
+    }
+
+    let mut files = fs::read_dir(path)
+        .and_then(|dir| {
+            dir.map(|e| e.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|source| ParseError::ReadError {
+            path: path.display().to_string(),
+            source,
+        })?;
+    files.retain(|f| f.is_file());
+    files.sort();
+    Ok(files)
+}
+
+fn plain_text_entries(path: &Path) -> Result<Vec<Entry>, ParseError> {
+    let mut entries = Vec::new();
+    for f in source_files(path, &ParserType::PlainText)? {
+        info!("Parsing {}.", f.display());
+        let mut builder = PlainText::builder(&f.display().to_string(), &read_source(&f)?)?;
+        // A file name such as "42 Abide with Me.txt" gives the song's number
+        let number = f.file_stem().and_then(|s| {
+            let s = s.to_string_lossy();
+            let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            s[..digits].parse().ok()
+        });
+        if let Some(number) = number {
+            builder = builder.set_number(number);
+        }
+        entries.push(Entry {
+            title: builder.title().to_owned(),
+            builder,
+            unknown_tags: Vec::new(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Reads `path` to a string, dropping any byte order mark.
+fn read_source(path: &Path) -> Result<String, ParseError> {
+    trace!("Reading {} to string.", path.display());
+    let buf = fs::read_to_string(path).map_err(|source| ParseError::ReadError {
+        path: path.display().to_string(),
+        source,
+    })?;
+    Ok(buf.trim_start_matches('\u{feff}').to_owned())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), ExportError> {
+    fs::write(path, contents).map_err(|source| ExportError::WriteError {
+        path: path.display().to_string(),
+        source,
+    })
+}
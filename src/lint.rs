@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    error::SongError,
+    parser::Entry,
+    song::{Song, StanzaType},
+};
+
+/// Represents a problem found in a song
+#[derive(Debug, Error)]
+pub enum Lint {
+    /// The song could not be built
+    #[error(transparent)]
+    SongError(#[from] SongError),
+
+    /// A VideoPsalm stanza tag which was skipped
+    #[error("Unknown VideoPsalm tag {0}, stanza skipped.")]
+    UnknownTag(i32),
+
+    /// A stanza with no text, and its number among stanzas of its type
+    #[error("{0} {1} is empty.")]
+    EmptyStanza(StanzaType, usize),
+
+    /// The title is used by more than one song
+    #[error("Title is used by {0} songs.")]
+    DuplicateTitle(usize),
+
+    /// The title has leading, trailing or doubled whitespace
+    #[error("Title has stray whitespace.")]
+    TitleWhitespace,
+
+    /// Text which looks like a word split by copying from a PDF, e.g. "L ORD"
+    #[error(r#"Suspicious text "{0}", possibly a split word."#)]
+    Artefact(String),
+}
+
+/// Represents a problem found in a particular song of a source
+#[derive(Debug)]
+pub struct Problem {
+    pub source: String,
+    pub title: String,
+    pub lint: Lint,
+}
+
+/// Checks every song of every source, given as `(source, entries)` pairs.
+///
+/// Invalid songs are consumed into [`Lint::SongError`]s.
+pub fn lint(sources: Vec<(String, Vec<Entry>)>) -> Vec<Problem> {
+    let mut titles: HashMap<String, usize> = HashMap::new();
+    for (_, entries) in &sources {
+        for e in entries {
+            *titles.entry(e.title.trim().to_lowercase()).or_default() += 1;
+        }
+    }
+
+    let mut problems = Vec::new();
+    for (source, entries) in sources {
+        for e in entries {
+            let mut push = |lint| {
+                problems.push(Problem {
+                    source: source.clone(),
+                    title: e.title.clone(),
+                    lint,
+                })
+            };
+
+            match titles.get(&e.title.trim().to_lowercase()) {
+                Some(&n) if n > 1 => push(Lint::DuplicateTitle(n)),
+                _ => {}
+            }
+            if e.title.trim() != e.title || e.title.contains("  ") {
+                push(Lint::TitleWhitespace);
+            }
+            for tag in e.unknown_tags {
+                push(Lint::UnknownTag(tag));
+            }
+            e.builder
+                .warnings()
+                .into_iter()
+                .for_each(|w| push(w.into()));
+            match e.builder.build() {
+                Ok(song) => lint_song(&song).into_iter().for_each(push),
+                Err(err) => push(err.into()),
+            }
+        }
+    }
+
+    problems
+}
+
+/// Checks the text of a song.
+fn lint_song(song: &Song) -> Vec<Lint> {
+    let mut lints = Vec::new();
+
+    let stanzas = song
+        .verses
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (StanzaType::Verse, i + 1, v))
+        .chain(song.chorus.iter().map(|c| (StanzaType::Chorus, 1, c)))
+        .chain(song.bridge.iter().map(|b| (StanzaType::Bridge, 1, b)));
+    for (kind, i, stanza) in stanzas {
+        if stanza.trim().is_empty() {
+            lints.push(Lint::EmptyStanza(kind, i));
+        }
+
+        for line in stanza.lines() {
+            lints.extend(artefacts(line).into_iter().map(Lint::Artefact));
+        }
+    }
+
+    lints
+}
+
+/// Finds words split in two, like "L ORD" or "lai d".
+fn artefacts(line: &str) -> Vec<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    words
+        .windows(2)
+        .filter(|pair| is_split_word(pair[0], pair[1]))
+        .map(|pair| pair.join(" "))
+        .collect()
+}
+
+/// Whether two neighbouring words look like one word split in two, like "L ORD" or "lai d".
+pub(crate) fn is_split_word(a: &str, b: &str) -> bool {
+    let letters = |w: &str| w.chars().filter(|c| c.is_alphabetic()).count();
+
+    // A lone capital followed by a capitalised word, e.g. "L ORD"
+    let split_caps = a.len() == 1
+        && !matches!(a, "A" | "I" | "O")
+        && a.chars().all(|c| c.is_uppercase())
+        && b.chars().take(2).filter(|c| c.is_uppercase()).count() == 2;
+
+    // A lone lowercase letter ending a word, e.g. "lai d"
+    let split_tail = letters(b) == 1
+        && b.chars()
+            .next()
+            .is_some_and(|c| c.is_lowercase() && !matches!(c, 'a' | 'i' | 'o'))
+        && letters(a) == a.chars().count()
+        && a.chars().next().is_some_and(|c| c.is_lowercase());
+
+    split_caps || split_tail
+}
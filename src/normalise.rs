@@ -0,0 +1,233 @@
+use log::info;
+
+use crate::{
+    config::{Normalise, Quotes},
+    lint::is_split_word,
+    song::Song,
+};
+
+/// Fixes artefacts in the text of a song, as configured, logging each change.
+///
+/// Stanzas are fixed line by line, and never gain or lose lines, so Bible verse numbers still line
+/// up with their lines.
+pub fn normalise(settings: &Normalise, song: &mut Song) {
+    let title = song.title.clone();
+    song.title = normalise_line(settings, &title, &title);
+
+    let stanzas = song
+        .verses
+        .iter_mut()
+        .chain(song.chorus.iter_mut())
+        .chain(song.bridge.iter_mut());
+    for stanza in stanzas {
+        let lines: Vec<String> = stanza
+            .lines()
+            .map(|line| normalise_line(settings, &title, line))
+            .collect();
+        *stanza = lines.join("\n");
+    }
+}
+
+/// Applies each enabled fix to a line, or a title, in turn.
+fn normalise_line(settings: &Normalise, title: &str, line: &str) -> String {
+    let mut line = line.to_owned();
+    if settings.whitespace {
+        line = fix(title, "whitespace", line, whitespace);
+    }
+    if settings.split_words {
+        line = fix(title, "split words", line, split_words);
+    }
+    match settings.quotes {
+        Quotes::Keep => {}
+        Quotes::Straight => line = fix(title, "quotes", line, straight_quotes),
+        Quotes::Curly => line = fix(title, "quotes", line, curly_quotes),
+    }
+    // Only the LaTeX shows LORD in small caps, so the text is left as it is
+    if settings.lord && small_caps_lord(&line, "") != line {
+        info!(
+            r#"Normalising LORD in {}: "{}" will print it in small caps."#,
+            title, line
+        );
+    }
+    line
+}
+
+/// Applies `f` to `line`, logging what changed.
+fn fix<F>(title: &str, what: &str, line: String, f: F) -> String
+where
+    F: FnOnce(&str) -> String,
+{
+    let fixed = f(&line);
+    if fixed != line {
+        info!(
+            r#"Normalising {} in {}: "{}" became "{}"."#,
+            what,
+            title,
+            line.replace('\u{feff}', "<BOM>").replace('\r', "<CR>"),
+            fixed
+        );
+    }
+    fixed
+}
+
+/// Drops byte order marks and carriage returns, and collapses runs of spaces.
+fn whitespace(line: &str) -> String {
+    line.replace(['\u{feff}', '\r'], "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Joins words split by copying from a PDF, e.g. "L ORD" or "lai d".
+fn split_words(line: &str) -> String {
+    let words: Vec<&str> = line.split(' ').collect();
+    let mut joined = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        match words.get(i + 1) {
+            Some(next) if is_split_word(words[i], next) => {
+                joined.push(format!("{}{}", words[i], next));
+                i += 2;
+            }
+            _ => {
+                joined.push(words[i].to_owned());
+                i += 1;
+            }
+        }
+    }
+    joined.join(" ")
+}
+
+fn straight_quotes(line: &str) -> String {
+    line.replace(['‘', '’'], "'").replace(['“', '”'], "\"")
+}
+
+/// Makes straight quotes curly: opening at the start of a word, otherwise closing, which also
+/// suits apostrophes such as "don’t".
+fn curly_quotes(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut curly = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let opening = i
+            .checked_sub(1)
+            .is_none_or(|p| chars[p].is_whitespace() || "([{—–-".contains(chars[p]));
+        curly.push(match c {
+            '\'' if opening && !is_elision(&chars[i + 1..]) => '‘',
+            '\'' => '’',
+            '"' if opening => '“',
+            '"' => '”',
+            c => c,
+        });
+    }
+    curly
+}
+
+/// Whether the text after a quote is a common elision, e.g. 'tis, which takes an apostrophe.
+fn is_elision(after: &[char]) -> bool {
+    let after: String = after.iter().take(6).collect::<String>().to_lowercase();
+    ["tis", "twas", "neath", "gainst", "midst"]
+        .iter()
+        .any(|e| after.starts_with(e))
+}
+
+/// Replaces the word LORD with `small_caps`, e.g. the LaTeX `\LORD{}`, leaving words such as
+/// LORDS alone.
+pub(crate) fn small_caps_lord(text: &str, small_caps: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(at) = rest.find("LORD") {
+        let before = rest[..at].chars().next_back();
+        let after = rest[at + 4..].chars().next();
+        let word =
+            !before.is_some_and(char::is_alphabetic) && !after.is_some_and(char::is_alphabetic);
+        out.push_str(&rest[..at]);
+        out.push_str(if word { small_caps } else { "LORD" });
+        rest = &rest[at + 4..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_is_tidied() {
+        assert_eq!(
+            whitespace("\u{feff}The Lord's  my shepherd,\r"),
+            "The Lord's my shepherd,"
+        );
+        assert_eq!(whitespace("  I'll not want.  "), "I'll not want.");
+    }
+
+    #[test]
+    fn split_words_are_joined() {
+        assert_eq!(
+            split_words("The L ORD's my shepherd"),
+            "The LORD's my shepherd"
+        );
+        assert_eq!(
+            split_words("And lai d Him in a manger"),
+            "And laid Him in a manger"
+        );
+        assert_eq!(split_words("A LORD of lords"), "A LORD of lords");
+        assert_eq!(split_words("I d on't know"), "I d on't know");
+    }
+
+    #[test]
+    fn quotes_are_made_curly() {
+        assert_eq!(
+            curly_quotes(r#"'Glory to the new-born King!' "Peace""#),
+            "‘Glory to the new-born King!’ “Peace”"
+        );
+        assert_eq!(curly_quotes("don't"), "don’t");
+        assert_eq!(curly_quotes("'Tis so sweet"), "’Tis so sweet");
+        assert_eq!(curly_quotes("("), "(");
+    }
+
+    #[test]
+    fn quotes_are_made_straight() {
+        assert_eq!(straight_quotes("“Peace”, don’t"), r#""Peace", don't"#);
+    }
+
+    #[test]
+    fn elisions() {
+        let is = |s: &str| is_elision(&s.chars().collect::<Vec<_>>());
+        assert!(is("tis so sweet"));
+        assert!(is("Twas in the moon"));
+        assert!(is("neath the cross"));
+        assert!(!is("Glory"));
+        assert!(!is(""));
+    }
+
+    #[test]
+    fn lord_as_a_word() {
+        assert_eq!(
+            small_caps_lord("The LORD's my shepherd, O LORD", "<L>"),
+            "The <L>'s my shepherd, O <L>"
+        );
+        assert_eq!(
+            small_caps_lord("LORDS and OVERLORD", "<L>"),
+            "LORDS and OVERLORD"
+        );
+    }
+
+    #[test]
+    fn songs_keep_their_lines() {
+        let mut song = Song::builder(" Psalm 23 ")
+            .add_verse("1 The L ORD's my  shepherd,\r\nI'll not want")
+            .set_order("v")
+            .build()
+            .unwrap();
+        normalise(
+            &Normalise {
+                quotes: Quotes::Curly,
+                ..Default::default()
+            },
+            &mut song,
+        );
+        assert_eq!(song.title, "Psalm 23");
+        assert_eq!(song.verses[0], "The LORD’s my shepherd,\nI’ll not want");
+    }
+}
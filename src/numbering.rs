@@ -0,0 +1,109 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use log::{trace, warn};
+
+use crate::{
+    config::{Config, Numbering},
+    error::SongSheetError,
+    song::Song,
+};
+
+/// Gives every song its number, as configured.
+///
+/// Songs in the lock file keep the number they were given before, and new songs are given numbers
+/// after the highest so far. The numbers of songs which have been removed are not reused.
+pub fn number_songs(config: &Config, songs: &mut [Song]) -> Result<(), SongSheetError> {
+    if config.numbering == Numbering::None {
+        songs.iter_mut().for_each(|s| s.number = None);
+        return Ok(());
+    }
+
+    let mut lock = match &config.number_lock {
+        Some(path) => load(Path::new(path))?,
+        None => BTreeMap::new(),
+    };
+    let keys = keys(songs);
+
+    let mut used: BTreeSet<u32> = lock.values().copied().collect();
+    let mut pending = Vec::new();
+    for (i, (song, key)) in songs.iter_mut().zip(&keys).enumerate() {
+        let source = song.number.take();
+        if let Some(&n) = lock.get(key) {
+            song.number = Some(n);
+            continue;
+        }
+        match source.filter(|_| config.numbering == Numbering::Source) {
+            Some(n) if used.insert(n) => song.number = Some(n),
+            Some(n) => {
+                warn!("{} has the same number as another song, {}.", song.title, n);
+                pending.push(i);
+            }
+            None => pending.push(i),
+        }
+    }
+
+    let next = used.last().map_or(1, |n| n + 1);
+    for (i, n) in pending.into_iter().zip(next..) {
+        trace!("Numbering {} as {}.", songs[i].title, n);
+        songs[i].number = Some(n);
+    }
+
+    if let Some(path) = &config.number_lock {
+        let before = lock.clone();
+        for (song, key) in songs.iter().zip(keys) {
+            lock.extend(song.number.map(|n| (key, n)));
+        }
+        if lock != before {
+            save(Path::new(path), &lock)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Names songs in the lock file by title, telling apart songs which share one with " (2)" etc.
+fn keys(songs: &[Song]) -> Vec<String> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    songs
+        .iter()
+        .map(|s| {
+            let n = seen.entry(&s.title).or_default();
+            *n += 1;
+            match n {
+                1 => s.title.clone(),
+                n => format!("{} ({})", s.title, n),
+            }
+        })
+        .collect()
+}
+
+fn load(path: &Path) -> Result<BTreeMap<String, u32>, SongSheetError> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let s = fs::read_to_string(path).map_err(|source| SongSheetError::ReadError {
+        path: path.display().to_string(),
+        source,
+    })?;
+    toml::from_str(&s).map_err(|source| SongSheetError::InvalidLock {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+fn save(path: &Path, lock: &BTreeMap<String, u32>) -> Result<(), SongSheetError> {
+    trace!("Writing {}.", path.display());
+    let s = format!(
+        "# Song numbers, kept the same from one build to the next. Remove a line to renumber that song.\n\n{}",
+        toml::to_string(lock).unwrap_or_default()
+    );
+    fs::write(path, s).map_err(|source| SongSheetError::WriteError {
+        path: path.display().to_string(),
+        source,
+    })
+}
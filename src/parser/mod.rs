@@ -1,18 +1,29 @@
 mod plain_text;
 mod video_psalm;
 
-use std::fmt::Display;
+use std::{fmt::Display, path::Path, str::FromStr};
 
 use serde::Deserialize;
 
+use crate::{error::ParseError, song::SongBuilder};
+
 pub use plain_text::PlainText;
-pub use video_psalm::video_psalm;
+pub use video_psalm::{is_video_psalm, video_psalm, video_psalm_entries};
+
+/// Represents a song as read from a source, before it is built and checked.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub title: String,
+    pub builder: SongBuilder,
+    /// Stanza tags the parser did not recognise and skipped
+    pub unknown_tags: Vec<i32>,
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub enum ParserType {
+    #[default]
     VideoPsalm,
     PlainText,
-    Unknown(String),
 }
 
 impl Display for ParserType {
@@ -20,7 +31,44 @@ impl Display for ParserType {
         match self {
             Self::VideoPsalm => f.write_str("VideoPsalm"),
             Self::PlainText => f.write_str("PlainText"),
-            Self::Unknown(s) => f.write_fmt(format_args!("Unknown: {}", s)),
+        }
+    }
+}
+
+impl ParserType {
+    /// Guesses the parser from a path: `.json` files are VideoPsalm song books, `.txt` files and
+    /// directories are plain text.
+    pub fn from_path<P>(path: &P) -> Option<Self>
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Some(Self::PlainText);
+        }
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(Self::VideoPsalm),
+            "txt" => Some(Self::PlainText),
+            _ => None,
+        }
+    }
+
+    /// Lowercases `s` and strips separators, so "Video Psalm" and "video_psalm" match.
+    fn normalise(s: &str) -> String {
+        let mut s = s.to_lowercase();
+        s.retain(|c| !matches!(c, ' ' | '-' | '_'));
+        s
+    }
+}
+
+impl FromStr for ParserType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Self::normalise(s).as_str() {
+            "videopsalm" => Ok(Self::VideoPsalm),
+            "plaintext" => Ok(Self::PlainText),
+            _ => Err(ParseError::UnknownParser(s.to_owned())),
         }
     }
 }
@@ -30,13 +78,12 @@ impl<'de> Deserialize<'de> for ParserType {
     where
         D: serde::Deserializer<'de>,
     {
-        let mut s = String::deserialize(deserializer)?.to_lowercase();
-        s.retain(|c| !matches!(c, ' ' | '-' | '_'));
-
-        Ok(match s.as_str() {
-            "videopsalm" => Self::VideoPsalm,
-            "plaintext" => Self::PlainText,
-            _ => Self::Unknown(s),
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(|_| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&s),
+                &r#""VideoPsalm" or "PlainText""#,
+            )
         })
     }
 }
@@ -1,24 +1,33 @@
-use colored::Colorize;
 use nom::{
     bytes::complete::{tag, take_until},
     error::Error,
     Finish, IResult,
 };
 
-use crate::song::{Song, StanzaType};
+use crate::{
+    error::{Location, ParseError},
+    song::{Song, SongBuilder, StanzaType},
+};
 // Parser types
 
 pub struct PlainText;
 impl PlainText {
-    pub fn parse(input: &str) -> Result<Song, String> {
+    /// Parses a song read from `path`. The path is only used to locate errors.
+    pub fn parse(path: &str, input: &str) -> Result<Song, ParseError> {
+        Ok(Self::builder(path, input)?.build()?)
+    }
+
+    /// Reads a song without checking it.
+    pub fn builder(path: &str, input: &str) -> Result<SongBuilder, ParseError> {
+        let source = input;
         let (mut input, name) = match Self::match_name(input) {
             Ok(ok) => ok,
             Err(_) => {
-                return Err(format!(
-                    "{}: Invalid song title.
-Song title must be separated by a blank line.",
-                    "SONG ERROR".red().bold()
-                ))
+                // Point at the line which should have been blank
+                let offset = source.find('\n').map_or(0, |i| i + 1);
+                return Err(ParseError::InvalidTitle {
+                    at: Location::from_offset(path, source, offset),
+                });
             }
         };
 
@@ -31,7 +40,12 @@ Song title must be separated by a blank line.",
             let (i, stanza_type) = match Self::start_tag(input).finish() {
                 Ok((i, _)) => match Self::identify_tag(i) {
                     Ok(ok) => ok,
-                    Err(_) => return Err(format!("{}: Invalid tag.", "SONG ERROR".red().bold())),
+                    Err(_) => {
+                        return Err(ParseError::InvalidTag {
+                            at: Location::from_offset(path, source, source.len() - input.len()),
+                            tag: input.lines().next().unwrap_or_default().to_owned(),
+                        })
+                    }
                 },
                 Err(e) => (e.input, StanzaType::Verse),
             };
@@ -57,7 +71,7 @@ Song title must be separated by a blank line.",
             input = i;
         }
 
-        Ok(song.set_order(&order).build().unwrap())
+        Ok(song.set_order(&order))
     }
 
     /// Matches song title, separated by a blank line
@@ -1,7 +1,11 @@
 use log::{info, trace, warn};
 use serde::Deserialize;
 
-use crate::{error::ParseError, Song};
+use super::Entry;
+use crate::{
+    error::{Location, ParseError},
+    Song,
+};
 
 /// Represents a VideoPsalm Song Book
 /// ```
@@ -19,10 +23,17 @@ struct VS {
 /// ```
 #[derive(Deserialize)]
 struct VsSong {
-    // #[serde(rename = "Author")]
-    // author: String,
-    // #[serde(rename = "Copyright")]
-    // copyright: String,
+    /// The song's number in VideoPsalm, which few songs have been given. Otherwise a song's
+    /// number is its position in the song book
+    #[serde(rename = "ID")]
+    number: Option<u32>,
+    #[serde(rename = "Author")]
+    author: Option<String>,
+    #[serde(rename = "Copyright")]
+    copyright: Option<String>,
+    /// Holds details of the music, e.g. "Music by William Henry Monk (Eventide)"
+    #[serde(rename = "Reference")]
+    reference: Option<String>,
     #[serde(rename = "Text")]
     title: String,
     #[serde(rename = "Verses")]
@@ -44,18 +55,59 @@ struct VsStanza {
     tag: Option<i32>,
 }
 
-pub fn video_psalm<T>(input: &T) -> Result<Vec<Song>, ParseError>
+/// Parses a VideoPsalm song book read from `path`. The path is only used to locate errors.
+pub fn video_psalm<T>(path: &str, input: &T) -> Result<Vec<Song>, ParseError>
+where
+    T: AsRef<str>,
+{
+    video_psalm_entries(path, input)?
+        .into_iter()
+        .map(|e| e.builder.build().map_err(ParseError::from))
+        .collect()
+}
+
+/// Parses a VideoPsalm song book, keeping the outcome of each song separate so one bad song
+/// does not hide the rest.
+pub fn video_psalm_entries<T>(path: &str, input: &T) -> Result<Vec<Entry>, ParseError>
 where
     T: AsRef<str>,
 {
     trace!("Parsing as json.");
-    let json: VS = serde_json::from_str(input.as_ref())?;
+    let input = input.as_ref();
+    let (strict, offsets) = strict_json(input);
+    let json: VS = serde_json::from_str(&strict).map_err(|source| {
+        // Point at the same place in the original
+        let offset = byte_offset(&strict, source.line(), source.column());
+        ParseError::JSONError {
+            at: Location::from_offset(path, input, offsets[offset]),
+            source,
+        }
+    })?;
 
     let mut ret = Vec::new();
-    for j in json.songs {
+    for (position, j) in (1..).zip(json.songs) {
         trace!("Creating new Song for {}.", &j.title);
-        let mut s = Song::builder(&j.title);
+        let mut s = Song::builder(&j.title).set_number(j.number.unwrap_or(position));
+        if let Some(author) = j.author.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+            s = s.set_author(author);
+        }
+        if let Some(copyright) = j
+            .copyright
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+        {
+            s = s.set_copyright(copyright);
+        }
+        let (composer, tune) = j.reference.as_deref().map(music).unwrap_or_default();
+        if let Some(composer) = composer {
+            s = s.set_composer(composer);
+        }
+        if let Some(tune) = tune {
+            s = s.set_tune(tune);
+        }
         let mut order = String::new();
+        let mut unknown_tags = Vec::new();
         trace!("Iterating over stanzas in {}.", &j.title);
         for stanza in &j.stanzas {
             match stanza.tag {
@@ -77,13 +129,356 @@ where
                         );
                     } else {
                         warn!("Unknown tag type {}.", tag);
+                        unknown_tags.push(tag);
                     }
                 }
             }
         }
-        s = s.set_order(&order);
-        ret.push(s.build()?);
+        ret.push(Entry {
+            builder: s.set_order(&order),
+            title: j.title,
+            unknown_tags,
+        });
     }
 
     Ok(ret)
 }
+
+/// Whether `input` is a VideoPsalm song book, without building its songs or logging anything.
+pub fn is_video_psalm(input: &str) -> bool {
+    serde_json::from_str::<VS>(&strict_json(input).0).is_ok()
+}
+
+/// Tunes which song books credit with "Music by", as if they were composers
+const TUNES: [&str; 2] = ["Lasst Uns Erfreuen", "Laast Uns Erfreuen"];
+
+/// Labels which start a field of a reference, e.g. "Music" in "Music by John Goss"
+const LABELS: [&str; 7] = [
+    "Music",
+    "Tune",
+    "Words",
+    "Translated",
+    "Versified",
+    "Arranged",
+    "Paraphrased",
+];
+
+/// Finds the composer and tune in a reference, e.g. "Music by William Henry Monk (Eventide)",
+/// "Tune: 'Hyfrydol' by Rowland Hugh Prichard" or "Music: New Britain".
+fn music(reference: &str) -> (Option<&str>, Option<&str>) {
+    let mut composer = None;
+    let mut tune = None;
+    for field in fields(reference) {
+        let (c, t) = if let Some(music) = field.strip_prefix("Music by ") {
+            music_by(music.trim())
+        } else if let Some(music) = field
+            .strip_prefix("Music:")
+            .or_else(|| field.strip_prefix("Tune:"))
+        {
+            named_tune(music.trim())
+        } else {
+            continue;
+        };
+        composer = composer.or(c);
+        tune = tune.or(t);
+    }
+    (composer, tune)
+}
+
+/// Splits a reference into fields, which are separated by "|" or by a full stop before a label,
+/// e.g. "Translated by William Henry Draper. Music by Laast Uns Erfreuen".
+fn fields(reference: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    for part in reference.split('|') {
+        let mut rest = part;
+        // Initials have full stops too, e.g. "William M. Runyan"
+        while let Some(at) = rest
+            .match_indices(". ")
+            .map(|(at, _)| at)
+            .find(|at| LABELS.iter().any(|l| rest[at + 2..].starts_with(l)))
+        {
+            fields.push(&rest[..at]);
+            rest = &rest[at + 2..];
+        }
+        fields.push(rest);
+    }
+
+    fields
+        .into_iter()
+        .map(|f| f.trim().trim_end_matches('.').trim_end())
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+/// Reads the credit after "Music by", which may hold the tune as an aside, e.g. "George Job
+/// Elvey (Tune: Diademata)", or be the tune itself, e.g. "Slane (Irish traditional melody)".
+fn music_by(music: &str) -> (Option<&str>, Option<&str>) {
+    let (name, aside) = aside(music);
+    match aside {
+        _ if TUNES.contains(&name) => (None, Some(name)),
+        Some(aside) if is_description(aside) => (None, Some(name)),
+        Some(aside) => {
+            let tune = aside.strip_prefix("Tune:").map_or(aside, str::trim);
+            (Some(name), Some(tune))
+        }
+        None => (Some(name), None),
+    }
+}
+
+/// Reads a tune, e.g. "Tune - Rutherford", "'Hyfrydol' by Rowland Hugh Prichard" or "Antioch
+/// (Handel)". Descriptions such as "Traditional Scottish Folk Tune" are not tunes.
+fn named_tune(music: &str) -> (Option<&str>, Option<&str>) {
+    let music = music.strip_prefix("Tune -").map_or(music, str::trim);
+
+    if let Some((name, composer)) = music.split_once(" by ") {
+        let name = name.trim().trim_matches(['\'', '‘', '’']);
+        // Drop the year, e.g. "Chretien Urhan, 1834"
+        let composer = match composer.rsplit_once(',') {
+            Some((c, year)) if year.trim().chars().all(|c| c.is_ascii_digit()) => c,
+            _ => composer,
+        };
+        return (Some(composer.trim()), Some(name));
+    }
+
+    let (name, composer) = aside(music);
+    (composer, (!is_description(name)).then_some(name))
+}
+
+/// Splits off an aside in brackets, e.g. "Eventide" in "William Henry Monk (Eventide)".
+fn aside(text: &str) -> (&str, Option<&str>) {
+    match text.split_once('(') {
+        Some((text, aside)) => (text.trim(), Some(aside.trim_end_matches(')').trim())),
+        None => (text.trim(), None),
+    }
+}
+
+/// Whether music is described rather than named, e.g. "Irish traditional melody".
+fn is_description(music: &str) -> bool {
+    let music = music.to_lowercase();
+    ["traditional", "melody", "folk"]
+        .iter()
+        .any(|w| music.contains(w))
+}
+
+/// Turns VideoPsalm's own export format into strict JSON, returning it along with the offset in
+/// `input` of each of its bytes.
+///
+/// VideoPsalm leaves keys unquoted, e.g. `{Songs:[{Author:"..."`, and separates lines with raw
+/// vertical tabs or newlines inside strings. Files which are already strict JSON are unchanged.
+fn strict_json(input: &str) -> (String, Vec<usize>) {
+    let mut strict = String::with_capacity(input.len());
+    let mut offsets = Vec::with_capacity(input.len() + 1);
+    let mut push = |strict: &mut String, s: &str, at: usize| {
+        strict.push_str(s);
+        offsets.extend(std::iter::repeat_n(at, s.len()));
+    };
+
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                // VideoPsalm's line separator
+                '\n' | '\u{b}' => {
+                    push(&mut strict, "\\n", i);
+                    continue;
+                }
+                c if c.is_control() => {
+                    push(&mut strict, &format!("\\u{:04x}", c as u32), i);
+                    continue;
+                }
+                _ => {}
+            }
+            push(&mut strict, c.encode_utf8(&mut [0; 4]), i);
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '\u{feff}' => continue,
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = i + c.len_utf8();
+                while let Some(&(j, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = j + c.len_utf8();
+                    chars.next();
+                }
+                let word = &input[i..end];
+                // A key, rather than true, false or null
+                if input[end..].trim_start().starts_with(':') {
+                    push(&mut strict, &format!("\"{}\"", word), i);
+                } else {
+                    push(&mut strict, word, i);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        push(&mut strict, c.encode_utf8(&mut [0; 4]), i);
+    }
+    offsets.push(input.len());
+    (strict, offsets)
+}
+
+/// Finds the byte offset of a line and column as given by serde_json, whose columns count bytes
+/// rather than characters.
+fn byte_offset(input: &str, line: usize, column: usize) -> usize {
+    let start: usize = input
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    let mut offset = (start + column.saturating_sub(1)).min(input.len());
+    while !input.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_videopsalm_export() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/SongBooks/Bon Accord Hymns 2.json"
+        );
+        let input = std::fs::read_to_string(path).unwrap();
+        let songs = video_psalm(path, &input).unwrap();
+
+        let titles: Vec<&str> = songs.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            [
+                "Hark! The Herald Angels Sing",
+                "Jesus, Joy of the Highest Heaven",
+                "O Come, O Come, Immanuel",
+                "O Little Town of Bethlehem",
+            ]
+        );
+        assert_eq!(
+            songs[0].verses[0].lines().collect::<Vec<_>>(),
+            [
+                "Hark! the herald-angels sing",
+                "'Glory to the new-born King!",
+                "Peace on earth, and mercy mild,",
+                "God and sinners reconciled.'",
+            ]
+        );
+        assert_eq!(songs[0].author.as_deref(), Some("Charles Wesley (1739)."));
+    }
+
+    #[test]
+    fn recognises_song_books() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/SongBooks/Bon Accord Hymns 2.json"
+        );
+        assert!(is_video_psalm(&std::fs::read_to_string(path).unwrap()));
+        assert!(!is_video_psalm(r#"{"Songs": 3}"#));
+        assert!(!is_video_psalm(r#"{"name": "package"}"#));
+    }
+
+    #[test]
+    fn strict_json_is_unchanged() {
+        let input = r#"{"Songs":[{"ID":3,"Text":"A: b","Verses":[]}],"Flag":true}"#;
+        assert_eq!(strict_json(input).0, input);
+    }
+
+    #[test]
+    fn numbers_from_the_song_book() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/VS/BonAccordHymns.json");
+        let input = std::fs::read_to_string(path).unwrap();
+        let songs = video_psalm(path, &input).unwrap();
+
+        // Given a number in VideoPsalm
+        let reasons = songs
+            .iter()
+            .find(|s| s.title == "Reasons (Bless The Lord)")
+            .unwrap();
+        assert_eq!(reasons.number, Some(10000));
+
+        // Numbered by position
+        assert_eq!(songs[0].number, Some(1));
+        assert_eq!(songs[4].number, Some(5));
+    }
+
+    #[test]
+    fn music_credits() {
+        let cases = [
+            (
+                "Music by George Job Elvey (Tune: Diademata)",
+                (Some("George Job Elvey"), Some("Diademata")),
+            ),
+            (
+                "Music by John Hughes (Tune: Cwm Rhondda)",
+                (Some("John Hughes"), Some("Cwm Rhondda")),
+            ),
+            ("Music: Tune - Rutherford", (None, Some("Rutherford"))),
+            ("Music: Tune - Maccabaeus", (None, Some("Maccabaeus"))),
+            (
+                "Versified by Eleanor Henrietta Hulla . Music by Slane (Irish traditional melody)",
+                (None, Some("Slane")),
+            ),
+            (
+                "Translated by William Henry Draper. Music by Laast Uns Erfreuen",
+                (None, Some("Laast Uns Erfreuen")),
+            ),
+            (
+                "Tune: 'Hyfrydol' by Rowland Hugh Prichard",
+                (Some("Rowland Hugh Prichard"), Some("Hyfrydol")),
+            ),
+            (
+                "Translated by John Mason Neale | Tune: Veni Emmanuel | Arranged by Thomas Helmore",
+                (None, Some("Veni Emmanuel")),
+            ),
+            (
+                "Tune: 'Rutherford' by Chretien Urhan, 1834",
+                (Some("Chretien Urhan"), Some("Rutherford")),
+            ),
+            (
+                "Tune: Dim Ond Iesu (Lowry)",
+                (Some("Lowry"), Some("Dim Ond Iesu")),
+            ),
+            (
+                "Music by William Henry Monk (Eventide)",
+                (Some("William Henry Monk"), Some("Eventide")),
+            ),
+            ("Music: New Britain", (None, Some("New Britain"))),
+            ("Music: Traditional Scottish Folk Tune", (None, None)),
+            (
+                "Music: To God Be The Glory (Doane)",
+                (Some("Doane"), Some("To God Be The Glory")),
+            ),
+            ("Tune: Antioch (Handel)", (Some("Handel"), Some("Antioch"))),
+            ("Music by Mendelssohn", (Some("Mendelssohn"), None)),
+            (
+                "Music by William M. Runyan",
+                (Some("William M. Runyan"), None),
+            ),
+            ("Translated by Robert Bridges", (None, None)),
+        ];
+        for (reference, expected) in cases {
+            assert_eq!(music(reference), expected, "{}", reference);
+        }
+    }
+
+    #[test]
+    fn error_column_counts_characters() {
+        let input = r#"{"Songs":[{"Text":"“Ô” ‘Sing’","Verses":[}]}"#;
+        let Err(ParseError::JSONError { at, .. }) = video_psalm_entries("test.json", &input) else {
+            panic!("expected a JSON error");
+        };
+        assert_eq!(at.line, 1);
+        let expected = input[..input.find("}]").unwrap()].chars().count() + 1;
+        assert_eq!(at.column, expected);
+    }
+}
@@ -0,0 +1,288 @@
+use std::fmt::Display;
+
+/// The books of the Bible in canonical order, each with the other names it goes by
+const BOOKS: [&[&str]; 66] = [
+    &["Genesis", "Gen"],
+    &["Exodus", "Exod", "Ex"],
+    &["Leviticus", "Lev"],
+    &["Numbers", "Num"],
+    &["Deuteronomy", "Deut"],
+    &["Joshua", "Josh"],
+    &["Judges", "Judg"],
+    &["Ruth"],
+    &["1 Samuel", "1 Sam"],
+    &["2 Samuel", "2 Sam"],
+    &["1 Kings", "1 Kgs"],
+    &["2 Kings", "2 Kgs"],
+    &["1 Chronicles", "1 Chron"],
+    &["2 Chronicles", "2 Chron"],
+    &["Ezra"],
+    &["Nehemiah", "Neh"],
+    &["Esther", "Esth"],
+    &["Job"],
+    &["Psalms", "Psalm", "Ps"],
+    &["Proverbs", "Prov"],
+    &["Ecclesiastes", "Eccl"],
+    &["Song of Songs", "Song of Solomon"],
+    &["Isaiah", "Isa"],
+    &["Jeremiah", "Jer"],
+    &["Lamentations", "Lam"],
+    &["Ezekiel", "Ezek"],
+    &["Daniel", "Dan"],
+    &["Hosea", "Hos"],
+    &["Joel"],
+    &["Amos"],
+    &["Obadiah", "Obad"],
+    &["Jonah"],
+    &["Micah", "Mic"],
+    &["Nahum", "Nah"],
+    &["Habakkuk", "Hab"],
+    &["Zephaniah", "Zeph"],
+    &["Haggai", "Hag"],
+    &["Zechariah", "Zech"],
+    &["Malachi", "Mal"],
+    &["Matthew", "Matt"],
+    &["Mark"],
+    &["Luke"],
+    &["John"],
+    &["Acts"],
+    &["Romans", "Rom"],
+    &["1 Corinthians", "1 Cor"],
+    &["2 Corinthians", "2 Cor"],
+    &["Galatians", "Gal"],
+    &["Ephesians", "Eph"],
+    &["Philippians", "Phil"],
+    &["Colossians", "Col"],
+    &["1 Thessalonians", "1 Thess"],
+    &["2 Thessalonians", "2 Thess"],
+    &["1 Timothy", "1 Tim"],
+    &["2 Timothy", "2 Tim"],
+    &["Titus"],
+    &["Philemon", "Philem"],
+    &["Hebrews", "Heb"],
+    &["James", "Jas"],
+    &["1 Peter", "1 Pet"],
+    &["2 Peter", "2 Pet"],
+    &["1 John"],
+    &["2 John"],
+    &["3 John"],
+    &["Jude"],
+    &["Revelation", "Rev"],
+];
+
+/// Represents a passage of scripture, e.g. Psalm 102:24b-28
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Position of the book in the canon, from 0 for Genesis
+    pub book: usize,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Represents a chapter, or a verse or part of a verse within it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub chapter: u32,
+    pub verse: Option<u32>,
+    /// Part of the verse, e.g. the b of 24b
+    pub part: Option<char>,
+}
+
+impl Reference {
+    /// Name of the book, e.g. Isaiah.
+    pub fn book_name(&self) -> &'static str {
+        BOOKS[self.book][0]
+    }
+
+    /// The chapters and verses without the book, e.g. 53:5-9.
+    pub fn passage(&self) -> String {
+        let mut s = self.start.to_string();
+        if self.end != self.start {
+            s.push('-');
+            if self.end.chapter == self.start.chapter && self.start.verse.is_some() {
+                s.push_str(&self.end.verse_string());
+            } else {
+                s.push_str(&self.end.to_string());
+            }
+        }
+        s
+    }
+}
+
+impl Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // A single psalm is a Psalm
+        let book = match self.book_name() {
+            "Psalms" if self.start.chapter == self.end.chapter => "Psalm",
+            book => book,
+        };
+        write!(f, "{} {}", book, self.passage())
+    }
+}
+
+impl Position {
+    fn verse_string(&self) -> String {
+        let mut s = self.verse.map(|v| v.to_string()).unwrap_or_default();
+        s.extend(self.part);
+        s
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.verse {
+            Some(_) => write!(f, "{}:{}", self.chapter, self.verse_string()),
+            None => write!(f, "{}", self.chapter),
+        }
+    }
+}
+
+/// Finds the references in a title, e.g. "Psalm 102:24b-28 (Sing Psalms)" or
+/// "Come Thou Long Expected Jesus & Psalm 67".
+///
+/// A book's name only counts when followed by a chapter, so "John" alone is not a reference.
+pub fn references(title: &str) -> Vec<Reference> {
+    let mut found = Vec::new();
+    let mut rest = title;
+    'outer: while !rest.is_empty() {
+        let at_word = title.len() == rest.len()
+            || !title[..title.len() - rest.len()].ends_with(|c: char| c.is_alphanumeric());
+        if at_word {
+            for (book, names) in BOOKS.iter().enumerate() {
+                for name in names.iter() {
+                    let Some(after) = rest.strip_prefix(name) else {
+                        continue;
+                    };
+                    let after = after.strip_prefix('.').unwrap_or(after);
+                    if let Some((reference, after)) = passage(book, after) {
+                        found.push(reference);
+                        rest = after;
+                        continue 'outer;
+                    }
+                }
+            }
+        }
+
+        let mut chars = rest.chars();
+        chars.next();
+        rest = chars.as_str();
+    }
+    found
+}
+
+/// Reads the chapters and verses after a book's name, returning what is left.
+fn passage(book: usize, input: &str) -> Option<(Reference, &str)> {
+    let input = input.strip_prefix(' ')?;
+    let (start, mut rest) = position(input, None)?;
+
+    let mut end = start;
+    if let Some(after) = rest.strip_prefix('-').or_else(|| rest.strip_prefix('–')) {
+        // "53:5-9" continues the chapter, "23-24" and "1:1-2:3" do not
+        let chapter = start.verse.map(|_| start.chapter);
+        if let Some((e, after)) = position(after, chapter) {
+            end = e;
+            rest = after;
+        }
+    }
+
+    Some((Reference { book, start, end }, rest))
+}
+
+/// Reads "chapter", "chapter:verse" or, if the chapter is already known, "verse".
+fn position(input: &str, chapter: Option<u32>) -> Option<(Position, &str)> {
+    let (first, rest) = number(input)?;
+
+    let (chapter, verse, rest) = match rest.strip_prefix(':').and_then(number) {
+        Some((verse, rest)) => (first, Some(verse), rest),
+        None => match chapter {
+            Some(chapter) => (chapter, Some(first), rest),
+            None => (first, None, rest),
+        },
+    };
+
+    // A letter for part of the verse, but not the start of a word
+    let mut chars = rest.chars();
+    let (part, rest) = match (verse, chars.next(), chars.next()) {
+        (Some(_), Some(c @ 'a'..='e'), next) if !next.is_some_and(char::is_alphanumeric) => {
+            (Some(c), &rest[1..])
+        }
+        _ => (None, rest),
+    };
+
+    Some((
+        Position {
+            chapter,
+            verse,
+            part,
+        },
+        rest,
+    ))
+}
+
+fn number(input: &str) -> Option<(u32, &str)> {
+    let end = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let n = input[..end].parse().ok()?;
+    Some((n, &input[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(title: &str) -> Vec<String> {
+        references(title).iter().map(|r| r.to_string()).collect()
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(found("Isaiah 53:5-9"), ["Isaiah 53:5-9"]);
+        assert_eq!(found("Genesis 1:1-2:3"), ["Genesis 1:1-2:3"]);
+        assert_eq!(found("Psalms 23-24"), ["Psalms 23-24"]);
+
+        let reference = &references("Isaiah 53:5-9")[0];
+        assert_eq!(reference.start.verse, Some(5));
+        assert_eq!(reference.end.chapter, 53);
+        assert_eq!(reference.end.verse, Some(9));
+    }
+
+    #[test]
+    fn part_verses() {
+        let reference = &references("Psalm 102:24b-28 (Sing Psalms)")[0];
+        assert_eq!(reference.to_string(), "Psalm 102:24b-28");
+        assert_eq!(reference.start.part, Some('b'));
+        assert_eq!(reference.end.part, None);
+
+        // Not part of a verse, but the start of a word
+        assert_eq!(found("Psalm 23 and more"), ["Psalm 23"]);
+    }
+
+    #[test]
+    fn abbreviations() {
+        assert_eq!(found("Ps 23"), ["Psalm 23"]);
+        assert_eq!(found("Rom. 8:28"), ["Romans 8:28"]);
+        assert_eq!(found("1 Cor 13"), ["1 Corinthians 13"]);
+        assert_eq!(found("1 John 4:7"), ["1 John 4:7"]);
+    }
+
+    #[test]
+    fn books_need_a_chapter() {
+        assert!(found("John").is_empty());
+        assert!(found("Amazing Grace").is_empty());
+        assert!(found("Jesus, Lamb of God").is_empty());
+        assert!(found("10,000 Reasons").is_empty());
+    }
+
+    #[test]
+    fn references_among_other_words() {
+        assert_eq!(
+            found("Come Thou Long Expected Jesus & Psalm 67"),
+            ["Psalm 67"]
+        );
+        assert_eq!(
+            found("Psalm 102:24b-28 (Sing Psalms)"),
+            ["Psalm 102:24b-28"]
+        );
+    }
+}
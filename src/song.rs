@@ -1,7 +1,12 @@
-use crate::error::SongError;
+use std::fmt::Display;
+
+use crate::{
+    error::SongError,
+    scripture::{self, Reference},
+};
 
 /// Represents the type of stanza
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum StanzaType {
     #[default]
     Verse,
@@ -9,12 +14,36 @@ pub enum StanzaType {
     Bridge,
 }
 
+impl Display for StanzaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Verse => f.write_str("Verse"),
+            Self::Chorus => f.write_str("Chorus"),
+            Self::Bridge => f.write_str("Bridge"),
+        }
+    }
+}
+
 /// Represents a Song
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Song {
+    /// Number in the song sheet, or in the source before numbering
+    pub number: Option<u32>,
     pub title: String,
+    pub author: Option<String>,
+    pub composer: Option<String>,
+    /// Name of the tune, e.g. Eventide
+    pub tune: Option<String>,
+    pub copyright: Option<String>,
+    /// Name of the section of the song sheet, after the source it came from
+    pub section: Option<String>,
+    /// Passages of scripture named in the title, e.g. Isaiah 53:5-9
+    pub references: Vec<Reference>,
     pub order: String,
     pub verses: Vec<String>,
+    /// Bible verse numbers from the start of each line of each verse, e.g. the 5 of "5 He grew
+    /// up like a tender shoot", taken out of the text. Empty unless the title has a reference.
+    pub verse_numbers: Vec<Vec<Option<u32>>>,
     pub chorus: Option<String>,
     pub bridge: Option<String>,
 }
@@ -34,11 +63,191 @@ impl Song {
             ..Default::default()
         }
     }
+
+    /// Number of distinct stanzas, not counting repeats.
+    pub fn stanza_count(&self) -> usize {
+        self.verses.len() + self.chorus.iter().count() + self.bridge.iter().count()
+    }
+
+    /// Whether the song is a psalm, going by the references in its title.
+    pub fn is_psalm(&self) -> bool {
+        self.references.iter().any(|r| r.book_name() == "Psalms")
+    }
+
+    /// The people who wrote the words or music, e.g. "Keith Getty & Stuart Townend" gives two.
+    pub fn writers(&self) -> Vec<String> {
+        let mut writers: Vec<String> = Vec::new();
+        for credit in self.author.iter().chain(self.composer.iter()) {
+            for name in names(credit) {
+                if !writers.contains(&name) {
+                    writers.push(name);
+                }
+            }
+        }
+        writers
+    }
+
+    /// Who to acknowledge for the song, or `None` if it is in the public domain or the copyright
+    /// is unknown.
+    ///
+    /// Songs administered on behalf of the owner are grouped under the administrator, as that is
+    /// who licences them.
+    pub fn rights_holder(&self) -> Option<String> {
+        let copyright = self.copyright.as_deref()?.trim();
+        if copyright.is_empty() || copyright.to_lowercase().contains("public domain") {
+            return None;
+        }
+
+        if let Some((_, admin)) = copyright.split_once("Admin. by ") {
+            let admin = admin
+                .split(['(', ')'])
+                .next()
+                .unwrap_or(admin)
+                .trim_matches(['/', ' ']);
+            if !admin.is_empty() {
+                return Some(admin.to_owned());
+            }
+        }
+
+        // Skip the copyright sign, years and labels, e.g. "© Copyright 1949 & 1953" or "© Words &
+        // Music: 1937"
+        let words: Vec<&str> = copyright.split_whitespace().collect();
+        let mut start = 0;
+        while let Some(&word) = words.get(start) {
+            let label = matches!(word, "Copyright" | "Copyright:" | "Words:" | "Music:")
+                || (word == "Words" && words.get(start + 1) == Some(&"&"));
+            if !label && word.chars().any(char::is_alphabetic) {
+                break;
+            }
+            start += 1;
+        }
+        let holder = &words[start..].join(" ");
+        // Keep the full stop of abbreviations such as "Inc."
+        let holder = match holder.rsplit(' ').next() {
+            Some(last) if last.len() > 4 => holder.trim_end_matches('.'),
+            _ => holder,
+        };
+        Some(if holder.is_empty() { copyright } else { holder }.to_owned())
+    }
+
+    /// The line the song starts with, which people often know it by.
+    pub fn first_line(&self) -> Option<&str> {
+        self.stanzas().find_map(|(_, text)| first_line(text))
+    }
+
+    /// The first line of the chorus, if it is sung.
+    pub fn chorus_first_line(&self) -> Option<&str> {
+        self.stanzas()
+            .find(|(kind, _)| *kind == StanzaType::Chorus)
+            .and_then(|(_, text)| first_line(text))
+    }
+
+    /// The Bible verse number at the start of a line of a verse, if there was one.
+    pub fn verse_number(&self, verse: usize, line: usize) -> Option<u32> {
+        self.verse_numbers.get(verse)?.get(line).copied().flatten()
+    }
+
+    /// Iterates over the stanzas in the order they are sung.
+    ///
+    /// Order characters without a matching stanza are skipped.
+    pub fn stanzas(&self) -> impl Iterator<Item = (StanzaType, &str)> {
+        let mut verses = self.verses.iter();
+        self.order.chars().filter_map(move |c| match c {
+            'v' => verses.next().map(|v| (StanzaType::Verse, v.as_str())),
+            'c' => self.chorus.as_deref().map(|c| (StanzaType::Chorus, c)),
+            'b' => self.bridge.as_deref().map(|b| (StanzaType::Bridge, b)),
+            _ => None,
+        })
+    }
+}
+
+/// Splits a credit such as "John Newton (1779) and John P. Rees ." into the names in it.
+fn names(credit: &str) -> Vec<String> {
+    // Drop asides such as "(1779)" or "(inc. Music)"
+    let mut plain = String::new();
+    let mut depth = 0;
+    for c in credit.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = (depth - 1).max(0),
+            c if depth == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+
+    plain
+        .replace(" and ", "&")
+        .split(['&', ','])
+        .map(|n| {
+            let n = n.trim().trim_end_matches('.').trim();
+            ["translated by ", "versified by ", "words by ", "music by "]
+                .iter()
+                .fold(n, |n, p| {
+                    if n.to_lowercase().starts_with(p) {
+                        &n[p.len()..]
+                    } else {
+                        n
+                    }
+                })
+                .trim()
+        })
+        // Leave out dates, e.g. "1880-1931"
+        .filter(|n| n.chars().any(char::is_alphabetic))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Takes the Bible verse numbers off the start of each line of a stanza, as in metrical psalms.
+///
+/// Only numbers followed by a space and some text count, so a line such as "10,000 reasons" is
+/// left alone.
+fn take_verse_numbers(stanza: &str) -> (Vec<Option<u32>>, String) {
+    let mut numbers = Vec::new();
+    let mut lines = Vec::new();
+    for line in stanza.lines() {
+        let trimmed = line.trim_start();
+        let digits = trimmed.len()
+            - trimmed
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        let rest = &trimmed[digits..];
+        let number = trimmed[..digits]
+            .parse::<u32>()
+            .ok()
+            // The longest psalm has 176 verses
+            .filter(|n| (1..=176).contains(n))
+            .filter(|_| rest.starts_with(' ') && !rest.trim().is_empty());
+        match number {
+            Some(n) => {
+                numbers.push(Some(n));
+                lines.push(rest.trim_start());
+            }
+            None => {
+                numbers.push(None);
+                lines.push(line);
+            }
+        }
+    }
+    (numbers, lines.join("\n"))
 }
 
-#[derive(Default)]
+/// The first line of a stanza with text, without trailing punctuation.
+fn first_line(stanza: &str) -> Option<&str> {
+    stanza
+        .lines()
+        .map(|l| l.trim().trim_end_matches([',', ';', ':']).trim_end())
+        .find(|l| !l.is_empty())
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct SongBuilder {
+    number: Option<u32>,
     title: String,
+    author: Option<String>,
+    composer: Option<String>,
+    tune: Option<String>,
+    copyright: Option<String>,
+    section: Option<String>,
     order: Option<String>,
     verses: Option<Vec<String>>,
     chorus: Option<String>,
@@ -46,6 +255,36 @@ pub struct SongBuilder {
 }
 
 impl SongBuilder {
+    pub fn set_author(mut self, author: &str) -> Self {
+        self.author = Some(author.to_owned());
+        self
+    }
+
+    pub fn set_number(mut self, number: u32) -> Self {
+        self.number = Some(number);
+        self
+    }
+
+    pub fn set_composer(mut self, composer: &str) -> Self {
+        self.composer = Some(composer.to_owned());
+        self
+    }
+
+    pub fn set_tune(mut self, tune: &str) -> Self {
+        self.tune = Some(tune.to_owned());
+        self
+    }
+
+    pub fn set_copyright(mut self, copyright: &str) -> Self {
+        self.copyright = Some(copyright.to_owned());
+        self
+    }
+
+    pub fn set_section(mut self, section: &str) -> Self {
+        self.section = Some(section.to_owned());
+        self
+    }
+
     pub fn add_verse(mut self, verse: &str) -> Self {
         self.verses.get_or_insert(Vec::new()).push(verse.to_owned());
         self
@@ -66,10 +305,131 @@ impl SongBuilder {
         self
     }
 
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Builds the song, repairing what it can rather than failing.
+    ///
+    /// Returns the song along with the errors which were repaired, or the first error which could
+    /// not be repaired.
+    pub fn build_lenient(mut self) -> Result<(Song, Vec<SongError>), SongError> {
+        let mut repaired = Vec::new();
+        loop {
+            match self.clone().build() {
+                Ok(song) => return Ok((song, repaired)),
+                Err(e) if self.repair(&e) => repaired.push(e),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Tries to fix the cause of `error`, returning whether anything changed.
+    ///
+    /// Every repair shortens or sets the order, so repeated repairs always finish.
+    fn repair(&mut self, error: &SongError) -> bool {
+        let verses = self.verses.as_ref().map_or(0, Vec::len);
+        let order = match error {
+            SongError::NoOrder { .. } => {
+                let mut order = "v".repeat(verses);
+                if self.chorus.is_some() {
+                    order.push('c');
+                }
+                if self.bridge.is_some() {
+                    order.push('b');
+                }
+                order
+            }
+            SongError::UnknownStanza { .. } => self
+                .order
+                .clone()
+                .unwrap_or_default()
+                .replace(|c| !matches!(c, 'v' | 'c' | 'b'), ""),
+            SongError::NoChorus { .. } => self.order.clone().unwrap_or_default().replace('c', ""),
+            SongError::NoBridge { .. } => self.order.clone().unwrap_or_default().replace('b', ""),
+            SongError::NotEnoughVerses { .. } => {
+                // Drop calls for verses past the last one
+                let mut seen = 0;
+                self.order
+                    .clone()
+                    .unwrap_or_default()
+                    .chars()
+                    .filter(|c| {
+                        seen += (*c == 'v') as usize;
+                        *c != 'v' || seen <= verses
+                    })
+                    .collect()
+            }
+            SongError::UnusedVerses { .. }
+            | SongError::UnusedChorus { .. }
+            | SongError::UnusedBridge { .. } => return false,
+        };
+
+        if order.is_empty() || self.order.as_ref() == Some(&order) {
+            return false;
+        }
+        self.order = Some(order);
+        true
+    }
+
+    /// Stanzas which are specified but never called for by the order.
+    ///
+    /// These do not stop the song from being built, so are left to the caller to report. A song
+    /// with no order has none, as that is an error of its own, and repairing it uses every stanza.
+    pub fn warnings(&self) -> Vec<SongError> {
+        let mut warnings = Vec::new();
+        let order = match self.order.as_deref() {
+            Some(order) if !order.is_empty() => order,
+            _ => return warnings,
+        };
+
+        let used = order.chars().filter(|c| *c == 'v').count();
+        let actual = self.verses.as_ref().map_or(0, Vec::len);
+        if used < actual {
+            warnings.push(SongError::UnusedVerses {
+                song_title: self.title.clone(),
+                used,
+                actual,
+            });
+        }
+
+        if self.chorus.is_some() && !order.contains('c') {
+            warnings.push(SongError::UnusedChorus {
+                song_title: self.title.clone(),
+            });
+        }
+
+        if self.bridge.is_some() && !order.contains('b') {
+            warnings.push(SongError::UnusedBridge {
+                song_title: self.title.clone(),
+            });
+        }
+
+        warnings
+    }
+
     pub fn build(self) -> Result<Song, SongError> {
-        let order = self.order.ok_or(SongError::NoOrder {
-            song_title: self.title.clone(),
-        })?;
+        let order = match &self.order {
+            Some(order) if !order.is_empty() => order.clone(),
+            _ => {
+                return Err(SongError::NoOrder {
+                    song_title: self.title,
+                })
+            }
+        };
+
+        // Check every stanza in the order is known
+        if let Some((position, found)) = order
+            .chars()
+            .enumerate()
+            .find(|(_, c)| !matches!(c, 'v' | 'c' | 'b'))
+        {
+            return Err(SongError::UnknownStanza {
+                song_title: self.title,
+                found,
+                position,
+            });
+        }
 
         // Check chorus
         if order.contains('c') && self.chorus.is_none() {
@@ -86,28 +446,142 @@ impl SongBuilder {
         }
 
         // Check there are enough verses
-        let verses = self.verses.ok_or(SongError::NotEnoughVerses {
-            song_title: self.title.clone(),
-            expected: 1,
-            actual: 0,
-        })?;
-        let v_act = verses.len();
-        let v_exp = order.chars().filter(|c| *c == 'v').count();
-
-        if v_exp < v_act {
+        let expected = order.chars().filter(|c| *c == 'v').count();
+        let actual = self.verses.as_ref().map_or(0, Vec::len);
+        if expected > actual {
             return Err(SongError::NotEnoughVerses {
                 song_title: self.title,
-                expected: v_exp,
-                actual: v_act,
+                expected,
+                actual,
             });
         }
 
+        // Only passages of scripture have verse numbers
+        let references = scripture::references(&self.title);
+        let mut verses = self.verses.unwrap_or_default();
+        let mut verse_numbers = Vec::new();
+        if !references.is_empty() {
+            for verse in &mut verses {
+                let (numbers, text) = take_verse_numbers(verse);
+                verse_numbers.push(numbers);
+                *verse = text;
+            }
+            if verse_numbers.iter().flatten().all(Option::is_none) {
+                verse_numbers.clear();
+            }
+        }
+
         Ok(Song {
+            references,
+            number: self.number,
             title: self.title,
+            author: self.author,
+            composer: self.composer,
+            tune: self.tune,
+            copyright: self.copyright,
+            section: self.section,
             order,
             verses,
+            verse_numbers,
             chorus: self.chorus,
             bridge: self.bridge,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rights_holders() {
+        let holder = |copyright: &str| {
+            Song {
+                copyright: Some(copyright.to_owned()),
+                ..Default::default()
+            }
+            .rights_holder()
+        };
+        let cases = [
+            (
+                "© Words: 1937 HarperCollins Religious",
+                Some("HarperCollins Religious"),
+            ),
+            (
+                "© Words & Music: 1995 Thankyou Music.",
+                Some("Thankyou Music"),
+            ),
+            ("© Copyright 1983 Thankyou Music", Some("Thankyou Music")),
+            ("© Copyright 2018 20schemes Music", Some("20schemes Music")),
+            (
+                "© 2011 Getty Music Publishing (Admin. by Music Services, Inc.)",
+                Some("Music Services, Inc."),
+            ),
+            ("© Copyright: © Words & Music: Public Domain", None),
+        ];
+        for (copyright, expected) in cases {
+            assert_eq!(holder(copyright).as_deref(), expected, "{}", copyright);
+        }
+    }
+
+    #[test]
+    fn verse_numbers_from_psalms() {
+        let (numbers, text) = take_verse_numbers(
+            "26 \"The earth and heavens will perish,\nbut you remain; they all wear out\n27 like clothing.",
+        );
+        assert_eq!(numbers, [Some(26), None, Some(27)]);
+        assert_eq!(
+            text,
+            "\"The earth and heavens will perish,\nbut you remain; they all wear out\nlike clothing."
+        );
+
+        let (numbers, text) = take_verse_numbers("1 Blessed is the man\n176 I have strayed");
+        assert_eq!(numbers, [Some(1), Some(176)]);
+        assert_eq!(text, "Blessed is the man\nI have strayed");
+    }
+
+    #[test]
+    fn verse_numbers_only_in_scripture() {
+        let song = |title: &str| {
+            Song::builder(title)
+                .add_verse("1 The Lord's my shepherd")
+                .set_order("v")
+                .build()
+                .unwrap()
+        };
+        assert_eq!(song("Psalm 23").verse_number(0, 0), Some(1));
+        assert_eq!(song("Psalm 23").verses[0], "The Lord's my shepherd");
+        assert_eq!(song("Crimond").verse_number(0, 0), None);
+        assert_eq!(song("Crimond").verses[0], "1 The Lord's my shepherd");
+    }
+
+    #[test]
+    fn verse_numbers_left_alone() {
+        let stanza = "0 Lord\n177 lines\n10,000 reasons for my heart to find\n12\n3 \n24b the rest";
+        let (numbers, text) = take_verse_numbers(stanza);
+        assert_eq!(numbers, [None; 6]);
+        assert_eq!(text, stanza);
+    }
+
+    #[test]
+    fn warnings_need_an_order() {
+        let builder = Song::builder("Song")
+            .add_verse("a")
+            .add_verse("b")
+            .set_chorus("c");
+        assert!(builder.clone().warnings().is_empty());
+
+        let warnings = builder.set_order("v").warnings();
+        assert!(matches!(
+            warnings[..],
+            [
+                SongError::UnusedVerses {
+                    used: 1,
+                    actual: 2,
+                    ..
+                },
+                SongError::UnusedChorus { .. }
+            ]
+        ));
+    }
+}
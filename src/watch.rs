@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use log::{info, trace};
+
+use crate::{
+    build, collect_songs,
+    config::{Config, Profile},
+    error::SongSheetError,
+    parse_entries,
+    parser::Entry,
+    report_mended, source_files,
+};
+
+/// Represents a source file which has been parsed
+struct Parsed {
+    modified: Option<SystemTime>,
+    /// `None` if the file could not be parsed
+    entries: Option<Vec<Entry>>,
+}
+
+/// Rebuilds the song sheet whenever the config file, a source or the cover image changes.
+///
+/// The config file is read with the given profile and overrides each time it changes, as by
+/// [`Config::load`].
+///
+/// Only the source files which changed are parsed again. Errors are passed to `report` once,
+/// rather than stopping the watch, and the song sheet is not built until they are fixed.
+pub fn watch<P, F>(
+    config_path: &P,
+    profile: Option<&str>,
+    overrides: &Profile,
+    interval: Duration,
+    mut report: F,
+) -> !
+where
+    P: AsRef<Path> + ?Sized,
+    F: FnMut(SongSheetError),
+{
+    let mut watcher = Watcher::new(config_path.as_ref(), profile, overrides);
+    loop {
+        watcher.poll(&mut report);
+        thread::sleep(interval);
+    }
+}
+
+/// Represents what has been read so far while watching
+struct Watcher<'a> {
+    config_path: &'a Path,
+    profile: Option<&'a str>,
+    overrides: &'a Profile,
+    /// `None` until the config file has been read, even if it could not be
+    config_modified: Option<Option<SystemTime>>,
+    cover_modified: Option<SystemTime>,
+    config: Option<Config>,
+    parsed: HashMap<PathBuf, Parsed>,
+    listing_failed: bool,
+}
+
+impl<'a> Watcher<'a> {
+    fn new(config_path: &'a Path, profile: Option<&'a str>, overrides: &'a Profile) -> Self {
+        Self {
+            config_path,
+            profile,
+            overrides,
+            config_modified: None,
+            cover_modified: None,
+            config: None,
+            parsed: HashMap::new(),
+            listing_failed: false,
+        }
+    }
+
+    /// Checks for changes once, rebuilding the song sheet if there were any.
+    fn poll<F>(&mut self, report: &mut F)
+    where
+        F: FnMut(SongSheetError),
+    {
+        let mut changed = false;
+
+        // Config, which is always read the first time, so a missing one is reported
+        let config_now = modified(self.config_path);
+        if self.config_modified != Some(config_now) {
+            self.config_modified = Some(config_now);
+            info!("Reading {}.", self.config_path.display());
+            match Config::load(self.config_path, self.profile, self.overrides) {
+                Ok(c) => {
+                    // Any source may now be read differently
+                    self.parsed.clear();
+                    self.config = Some(c);
+                    changed = true;
+                }
+                Err(e) => report(e.into()),
+            }
+        }
+
+        let Some(config) = &self.config else { return };
+
+        // Cover image
+        let cover_now = config.cover_image.as_deref().and_then(modified);
+        if cover_now != self.cover_modified {
+            self.cover_modified = cover_now;
+            changed = true;
+        }
+
+        // Sources
+        let files = match list_sources(config) {
+            Ok(files) => {
+                self.listing_failed = false;
+                files
+            }
+            Err(e) => {
+                if !self.listing_failed {
+                    report(e);
+                }
+                self.listing_failed = true;
+                return;
+            }
+        };
+        changed |= update_sources(config, &files, &mut self.parsed, report);
+
+        if !changed {
+            return;
+        }
+        let entries: Option<Vec<Entry>> = files
+            .iter()
+            .map(|f| self.parsed.get(f).and_then(|p| p.entries.clone()))
+            .collect::<Option<Vec<Vec<Entry>>>>()
+            .map(|e| e.into_iter().flatten().collect());
+        match entries {
+            Some(entries) => {
+                if let Err(e) = rebuild(config, entries) {
+                    report(e);
+                }
+            }
+            None => info!("Waiting for errors to be fixed."),
+        }
+        info!("Watching for changes.");
+    }
+}
+
+/// Every file of every source, in order.
+fn list_sources(config: &Config) -> Result<Vec<PathBuf>, SongSheetError> {
+    let mut files = Vec::new();
+    for source in &config.source {
+        files.append(&mut source_files(Path::new(source), &config.from)?);
+    }
+    Ok(files)
+}
+
+/// Parses the source files which are new or have changed since they were last parsed, returning
+/// whether there were any.
+fn update_sources<F>(
+    config: &Config,
+    files: &[PathBuf],
+    parsed: &mut HashMap<PathBuf, Parsed>,
+    report: &mut F,
+) -> bool
+where
+    F: FnMut(SongSheetError),
+{
+    // Forget files which have been removed
+    let before = parsed.len();
+    parsed.retain(|f, _| files.contains(f));
+    let mut changed = parsed.len() != before;
+
+    for f in files {
+        let modified = modified(f);
+        if parsed.get(f).is_some_and(|p| p.modified == modified) {
+            continue;
+        }
+
+        trace!("{} changed.", f.display());
+        let entries = match parse_entries(f, &config.from) {
+            Ok(entries) => Some(entries),
+            Err(e) => {
+                report(e.into());
+                None
+            }
+        };
+        parsed.insert(f.clone(), Parsed { modified, entries });
+        changed = true;
+    }
+
+    changed
+}
+
+/// Builds the song sheet from songs which have already been parsed.
+fn rebuild(config: &Config, entries: Vec<Entry>) -> Result<(), SongSheetError> {
+    let (songs, mended) = collect_songs(config, entries)?;
+    build(config, songs)?;
+    report_mended(&mended);
+    info!("Done!");
+    Ok(())
+}
+
+fn modified<P>(path: &P) -> Option<SystemTime>
+where
+    P: AsRef<Path> + ?Sized,
+{
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ConfigError;
+
+    #[test]
+    fn missing_config_is_reported_once() {
+        let overrides = Profile::default();
+        let mut watcher = Watcher::new(Path::new("no such config"), None, &overrides);
+        let mut errors = Vec::new();
+        watcher.poll(&mut |e| errors.push(e));
+        watcher.poll(&mut |e| errors.push(e));
+
+        assert!(matches!(
+            &errors[..],
+            [SongSheetError::ConfigError(ConfigError::ReadError { path, .. })]
+                if path == "no such config"
+        ));
+    }
+}